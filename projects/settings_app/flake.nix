@@ -40,7 +40,9 @@
           pname = "hyprland-settings";
           version = "0.1.0";
 
-          src = ./.;
+          # wonderland-theme is a path dependency in ../wonderland-desktop
+          src = ./..;
+          buildAndTestSubdir = "settings_app";
 
           cargoLock = {
             lockFile = ./Cargo.lock;
@@ -49,7 +51,7 @@
           inherit nativeBuildInputs buildInputs;
 
           postInstall = ''
-            install -Dm644 assets/hyprland-settings.desktop $out/share/applications/hyprland-settings.desktop
+            install -Dm644 settings_app/assets/hyprland-settings.desktop $out/share/applications/hyprland-settings.desktop
           '';
         };
 
@@ -1,8 +1,10 @@
+use futures::StreamExt;
 use gtk4::{prelude::*, *};
 use libadwaita as adw;
 use libadwaita::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
+use wonderland_theme::{Theme, ThemeLoader, ThemeWatcher, Variant};
 
 use crate::ui::{sidebar::Sidebar, pages::*};
 use crate::config::ConfigManager;
@@ -12,6 +14,7 @@ pub struct HyprlandSettings {
     window: ApplicationWindow,
     config_manager: Rc<RefCell<ConfigManager>>,
     current_page: Rc<RefCell<String>>,
+    theme_provider: Rc<RefCell<CssProvider>>,
 }
 
 impl HyprlandSettings {
@@ -25,15 +28,22 @@ impl HyprlandSettings {
             .decorated(false)  // No title bar
             .build();
 
-        // Apply custom CSS for styling
+        // Apply custom CSS for styling, colored by the Wonderland theme
         Self::load_css();
+        let theme = ThemeLoader::new()
+            .map(|loader| loader.load_current_or_builtin())
+            .unwrap_or_else(|_| wonderland_theme::default_theme());
+        let theme_provider = Rc::new(RefCell::new(Self::apply_theme(&theme, None)));
 
-        Self {
+        let settings = Self {
             app: app.clone(),
             window,
             config_manager: Rc::new(RefCell::new(ConfigManager::new())),
             current_page: Rc::new(RefCell::new("appearance".to_string())),
-        }
+            theme_provider,
+        };
+        settings.watch_theme();
+        settings
     }
 
     pub fn build_ui(&self) {
@@ -214,6 +224,7 @@ impl HyprlandSettings {
         header.add_controller(drag_controller);
     }
 
+    /// Structural styles; the colors come from `apply_theme`
     fn load_css() {
         let css_provider = CssProvider::new();
         css_provider.load_from_string(include_str!("styles.css"));
@@ -224,4 +235,86 @@ impl HyprlandSettings {
             gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
     }
+
+    /// Install a provider with the theme's colors in place of `previous`
+    fn apply_theme(theme: &Theme, previous: Option<&CssProvider>) -> CssProvider {
+        let display = gdk::Display::default().expect("Could not get default display");
+
+        let css_provider = CssProvider::new();
+        css_provider.load_from_string(&theme_css(theme));
+        gtk4::style_context_add_provider_for_display(
+            &display,
+            &css_provider,
+            gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+        if let Some(previous) = previous {
+            gtk4::style_context_remove_provider_for_display(&display, previous);
+        }
+
+        // Keep libadwaita's own widgets on the same side
+        adw::StyleManager::default().set_color_scheme(match theme.meta.variant {
+            Variant::Light => adw::ColorScheme::ForceLight,
+            Variant::Dark => adw::ColorScheme::ForceDark,
+        });
+
+        css_provider
+    }
+
+    /// Restyle the window whenever the active Wonderland theme changes
+    fn watch_theme(&self) {
+        let (tx, mut rx) = futures::channel::mpsc::channel(4);
+
+        // The watcher needs tokio; GTK owns the main thread
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    tracing::warn!("Theme watcher failed to start: {}", e);
+                    return;
+                }
+            };
+            let result = runtime.block_on(async move {
+                ThemeWatcher::new(ThemeLoader::new()?).run(tx).await
+            });
+            if let Err(e) = result {
+                tracing::warn!("Theme watcher stopped: {}", e);
+            }
+        });
+
+        let theme_provider = self.theme_provider.clone();
+        glib::MainContext::default().spawn_local(async move {
+            while let Some(theme) = rx.next().await {
+                let next = Self::apply_theme(&theme, Some(&theme_provider.borrow()));
+                *theme_provider.borrow_mut() = next;
+            }
+        });
+    }
+}
+
+/// The named colors styles.css uses, from the theme's roles
+fn theme_css(theme: &Theme) -> String {
+    let colors = [
+        ("window_bg_color", theme.background),
+        ("window_fg_color", theme.foreground),
+        ("view_bg_color", theme.background),
+        ("view_fg_color", theme.foreground),
+        ("card_bg_color", theme.surface),
+        ("sidebar_bg_color", theme.surface),
+        ("headerbar_bg_color", theme.surface),
+        ("headerbar_backdrop_color", theme.background),
+        ("headerbar_fg_color", theme.foreground),
+        ("borders", theme.border),
+        ("accent_color", theme.primary),
+        ("accent_bg_color", theme.primary),
+        ("accent_fg_color", theme.background),
+        ("muted_fg_color", theme.text_muted),
+        ("error_color", theme.error),
+        ("warning_color", theme.warning),
+        ("success_color", theme.success),
+    ];
+
+    colors
+        .iter()
+        .map(|(name, color)| format!("@define-color {} {};\n", name, color.to_css()))
+        .collect()
 }
\ No newline at end of file
@@ -7,6 +7,8 @@ use libadwaita as adw;
 use libadwaita::prelude::*;
 
 fn main() {
+    tracing_subscriber::fmt::init();
+
     // Initialize GTK and Adwaita
     gtk4::init().expect("Failed to initialize GTK");
 
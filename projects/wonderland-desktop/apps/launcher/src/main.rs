@@ -3,18 +3,21 @@
 //! Themeable application launcher for Wayland.
 
 use iced::widget::{column, container, row, scrollable, text, text_input, Column};
-use iced::{Element, Length, Task};
-use wonderland_theme::{ContainerClass, ThemeLoader, WonderlandTheme};
+use iced::{Element, Length, Subscription, Task};
+use std::time::{Duration, Instant};
+use wonderland_theme::{Easing, ThemeLoader, WonderlandTheme};
 
 fn main() -> iced::Result {
     tracing_subscriber::fmt::init();
 
     iced::application("Wonderland Launcher", App::update, App::view)
+        .theme(|app| app.theme.clone())
+        .subscription(App::subscription)
         .run_with(App::new)
 }
 
 struct App {
-    _theme: WonderlandTheme,
+    theme: WonderlandTheme,
     search_query: String,
     apps: Vec<AppEntry>,
     filtered_apps: Vec<usize>,
@@ -35,33 +38,18 @@ enum Message {
     SelectNext,
     SelectPrev,
     Exit,
+    ThemeChanged(Box<wonderland_theme::Theme>),
+    ThemeFrame(Instant),
 }
 
 impl App {
     fn new() -> (Self, Task<Message>) {
         // Load theme
         let loader = ThemeLoader::default();
-        let theme_data = loader.load_current().unwrap_or_else(|e| {
-            tracing::warn!("Failed to load theme: {}, using fallback", e);
-            // Fallback theme
-            wonderland_theme::Theme {
-                name: "fallback".to_string(),
-                path: std::path::PathBuf::new(),
-                foreground: wonderland_theme::Color::from_hex("#cdd6f4").unwrap(),
-                background: wonderland_theme::Color::from_hex("#1e1e2e").unwrap(),
-                primary: wonderland_theme::Color::from_hex("#89b4fa").unwrap(),
-                secondary: wonderland_theme::Color::from_hex("#74c7ec").unwrap(),
-                surface: wonderland_theme::Color::from_hex("#313244").unwrap(),
-                error: wonderland_theme::Color::from_hex("#f38ba8").unwrap(),
-                warning: wonderland_theme::Color::from_hex("#f9e2af").unwrap(),
-                success: wonderland_theme::Color::from_hex("#a6e3a1").unwrap(),
-                border: wonderland_theme::Color::from_hex("#45475a").unwrap(),
-                border_active: wonderland_theme::Color::from_hex("#89b4fa").unwrap(),
-                text_muted: wonderland_theme::Color::from_hex("#6c7086").unwrap(),
-            }
-        });
+        let theme_data = loader.load_current_or_builtin();
 
-        let theme = WonderlandTheme::new(theme_data);
+        let theme = WonderlandTheme::new(theme_data)
+            .with_transition(Duration::from_millis(300), Easing::EaseInOut);
 
         // TODO: Load actual desktop entries
         let apps = vec![
@@ -96,7 +84,7 @@ impl App {
 
         (
             Self {
-                _theme: theme,
+                theme,
                 search_query: String::new(),
                 apps,
                 filtered_apps,
@@ -138,18 +126,32 @@ impl App {
             Message::Exit => {
                 return iced::exit();
             }
+            Message::ThemeChanged(theme) => {
+                self.theme.set_theme(*theme, Instant::now());
+            }
+            Message::ThemeFrame(now) => {
+                self.theme.tick(now);
+            }
         }
         Task::none()
     }
 
-    fn view(&self) -> Element<Message> {
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            wonderland_theme::watcher::subscription()
+                .map(|theme| Message::ThemeChanged(Box::new(theme))),
+            self.theme.frames().map(Message::ThemeFrame),
+        ])
+    }
+
+    fn view(&self) -> Element<'_, Message, WonderlandTheme> {
         let search = text_input("Search...", &self.search_query)
             .id(text_input::Id::new("search"))
             .on_input(Message::SearchChanged)
             .padding(12)
             .size(18);
 
-        let results: Column<Message> = self
+        let results: Column<Message, WonderlandTheme> = self
             .filtered_apps
             .iter()
             .enumerate()
@@ -15,6 +15,39 @@ pub fn config_dir() -> PathBuf {
         })
 }
 
+/// Get the standard state directory for wonderland apps
+/// (`$XDG_STATE_HOME/wonderland`), for data that must stay writable
+pub fn state_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "wonderland")
+        .and_then(|d| d.state_dir().map(|dir| dir.to_path_buf()))
+        .unwrap_or_else(|| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".local").join("state").join("wonderland")
+        })
+}
+
+/// Get the standard cache directory for wonderland apps
+/// (`$XDG_CACHE_HOME/wonderland`), for data that can be regenerated
+pub fn cache_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "wonderland")
+        .map(|d| d.cache_dir().to_path_buf())
+        .unwrap_or_else(|| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".cache").join("wonderland")
+        })
+}
+
+/// Get the user's config directory (`$XDG_CONFIG_HOME`, usually
+/// `~/.config`), for writing other programs' configs
+pub fn xdg_config_home() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|d| d.config_dir().to_path_buf())
+        .unwrap_or_else(|| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".config")
+        })
+}
+
 /// Load a TOML config file
 pub fn load<T: DeserializeOwned>(name: &str) -> Result<T, ConfigError> {
     let path = config_dir().join(format!("{}.toml", name));
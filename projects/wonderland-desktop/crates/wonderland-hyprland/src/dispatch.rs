@@ -0,0 +1,335 @@
+//! Typed dispatchers
+//!
+//! [`Dispatch`] formats to the `dispatcher args` text Hyprland expects, so
+//! `HyprlandClient::dispatch` and `HyprlandClient::batch` don't need
+//! hand-written command strings.
+
+use crate::HyprlandError;
+use std::fmt;
+
+/// A window, as dispatchers select them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowSelector {
+    /// `0x`-prefixed, as in [`crate::Window::address`]
+    Address(String),
+    /// Regex on the class
+    Class(String),
+    /// Regex on the class the window was opened with
+    InitialClass(String),
+    /// Regex on the title
+    Title(String),
+    /// Regex on the title the window was opened with
+    InitialTitle(String),
+    Pid(i32),
+    Tag(String),
+    /// The first floating window on the current workspace
+    Floating,
+    /// The first tiled window on the current workspace
+    Tiled,
+}
+
+impl fmt::Display for WindowSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowSelector::Address(address) => write!(f, "address:{}", address),
+            WindowSelector::Class(class) => write!(f, "class:{}", class),
+            WindowSelector::InitialClass(class) => write!(f, "initialclass:{}", class),
+            WindowSelector::Title(title) => write!(f, "title:{}", title),
+            WindowSelector::InitialTitle(title) => write!(f, "initialtitle:{}", title),
+            WindowSelector::Pid(pid) => write!(f, "pid:{}", pid),
+            WindowSelector::Tag(tag) => write!(f, "tag:{}", tag),
+            WindowSelector::Floating => f.write_str("floating"),
+            WindowSelector::Tiled => f.write_str("tiled"),
+        }
+    }
+}
+
+/// A workspace, as dispatchers select them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceSelector {
+    Id(i32),
+    /// Offset from the current workspace's ID, e.g. `+1`
+    Relative(i32),
+    /// Offset among the workspaces on the current monitor
+    MonitorRelative(i32),
+    /// Offset among the open workspaces
+    OpenRelative(i32),
+    Name(String),
+    /// The previously focused workspace
+    Previous,
+    /// The first empty workspace
+    Empty,
+    /// The special workspace with the given name, or the unnamed one
+    Special(Option<String>),
+}
+
+impl fmt::Display for WorkspaceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceSelector::Id(id) => write!(f, "{}", id),
+            WorkspaceSelector::Relative(offset) => write!(f, "{:+}", offset),
+            WorkspaceSelector::MonitorRelative(offset) => write!(f, "m{:+}", offset),
+            WorkspaceSelector::OpenRelative(offset) => write!(f, "e{:+}", offset),
+            WorkspaceSelector::Name(name) => write!(f, "name:{}", name),
+            WorkspaceSelector::Previous => f.write_str("previous"),
+            WorkspaceSelector::Empty => f.write_str("empty"),
+            WorkspaceSelector::Special(None) => f.write_str("special"),
+            WorkspaceSelector::Special(Some(name)) => write!(f, "special:{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Direction::Left => "l",
+            Direction::Right => "r",
+            Direction::Up => "u",
+            Direction::Down => "d",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Fullscreen,
+    /// Fill the workspace, keeping bars and gaps
+    Maximize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupLock {
+    Lock,
+    Unlock,
+    Toggle,
+}
+
+/// A Hyprland dispatcher with its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Run a shell command, with window rules for the windows it opens,
+    /// e.g. `workspace 2 silent` or `float`
+    Exec { command: String, rules: Vec<String> },
+    /// Run a shell command without rules
+    ExecRaw(String),
+    Workspace(WorkspaceSelector),
+    /// Move a window (the active one if `None`) and follow it
+    MoveToWorkspace {
+        workspace: WorkspaceSelector,
+        window: Option<WindowSelector>,
+    },
+    /// Move a window (the active one if `None`) without following it
+    MoveToWorkspaceSilent {
+        workspace: WorkspaceSelector,
+        window: Option<WindowSelector>,
+    },
+    ToggleSpecialWorkspace(Option<String>),
+    RenameWorkspace { id: i32, name: String },
+    FocusWindow(WindowSelector),
+    MoveFocus(Direction),
+    /// Move the active window within the layout
+    MoveWindow(Direction),
+    SwapWindow(Direction),
+    /// Grow (or shrink, if negative) the active window by pixels
+    ResizeActive { x: i32, y: i32 },
+    /// Move the active floating window by pixels
+    MoveActive { x: i32, y: i32 },
+    CenterWindow,
+    /// The active window if `None`
+    ToggleFloating(Option<WindowSelector>),
+    Fullscreen(FullscreenMode),
+    /// Pin a floating window (the active one if `None`) to all workspaces
+    Pin(Option<WindowSelector>),
+    KillActive,
+    CloseWindow(WindowSelector),
+    FocusMonitor(String),
+    MoveCurrentWorkspaceToMonitor(String),
+    ToggleGroup,
+    /// Focus the next (or previous) window of the active group
+    ChangeGroupActive { forward: bool },
+    MoveIntoGroup(Direction),
+    /// The active window if `None`
+    MoveOutOfGroup(Option<WindowSelector>),
+    MoveWindowOrGroup(Direction),
+    LockActiveGroup(GroupLock),
+    /// Enter a submap, `reset` for the default one
+    Submap(String),
+    /// Any other dispatcher
+    Custom { dispatcher: String, args: String },
+}
+
+impl fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let with_window = |f: &mut fmt::Formatter<'_>, dispatcher: &str, window: &Option<WindowSelector>| match window {
+            Some(window) => write!(f, "{} {}", dispatcher, window),
+            None => f.write_str(dispatcher),
+        };
+
+        match self {
+            Dispatch::Exec { command, rules } if rules.is_empty() => write!(f, "exec {}", command),
+            Dispatch::Exec { command, rules } => write!(f, "exec [{}] {}", rules.join(";"), command),
+            Dispatch::ExecRaw(command) => write!(f, "execr {}", command),
+            Dispatch::Workspace(workspace) => write!(f, "workspace {}", workspace),
+            Dispatch::MoveToWorkspace { workspace, window } => {
+                write!(f, "movetoworkspace {}", workspace)?;
+                window.iter().try_for_each(|window| write!(f, ",{}", window))
+            }
+            Dispatch::MoveToWorkspaceSilent { workspace, window } => {
+                write!(f, "movetoworkspacesilent {}", workspace)?;
+                window.iter().try_for_each(|window| write!(f, ",{}", window))
+            }
+            Dispatch::ToggleSpecialWorkspace(None) => f.write_str("togglespecialworkspace"),
+            Dispatch::ToggleSpecialWorkspace(Some(name)) => write!(f, "togglespecialworkspace {}", name),
+            Dispatch::RenameWorkspace { id, name } => write!(f, "renameworkspace {} {}", id, name),
+            Dispatch::FocusWindow(window) => write!(f, "focuswindow {}", window),
+            Dispatch::MoveFocus(direction) => write!(f, "movefocus {}", direction),
+            Dispatch::MoveWindow(direction) => write!(f, "movewindow {}", direction),
+            Dispatch::SwapWindow(direction) => write!(f, "swapwindow {}", direction),
+            Dispatch::ResizeActive { x, y } => write!(f, "resizeactive {} {}", x, y),
+            Dispatch::MoveActive { x, y } => write!(f, "moveactive {} {}", x, y),
+            Dispatch::CenterWindow => f.write_str("centerwindow"),
+            Dispatch::ToggleFloating(window) => with_window(f, "togglefloating", window),
+            Dispatch::Fullscreen(FullscreenMode::Fullscreen) => f.write_str("fullscreen 0"),
+            Dispatch::Fullscreen(FullscreenMode::Maximize) => f.write_str("fullscreen 1"),
+            Dispatch::Pin(window) => with_window(f, "pin", window),
+            Dispatch::KillActive => f.write_str("killactive"),
+            Dispatch::CloseWindow(window) => write!(f, "closewindow {}", window),
+            Dispatch::FocusMonitor(monitor) => write!(f, "focusmonitor {}", monitor),
+            Dispatch::MoveCurrentWorkspaceToMonitor(monitor) => {
+                write!(f, "movecurrentworkspacetomonitor {}", monitor)
+            }
+            Dispatch::ToggleGroup => f.write_str("togglegroup"),
+            Dispatch::ChangeGroupActive { forward: true } => f.write_str("changegroupactive f"),
+            Dispatch::ChangeGroupActive { forward: false } => f.write_str("changegroupactive b"),
+            Dispatch::MoveIntoGroup(direction) => write!(f, "moveintogroup {}", direction),
+            Dispatch::MoveOutOfGroup(window) => with_window(f, "moveoutofgroup", window),
+            Dispatch::MoveWindowOrGroup(direction) => write!(f, "movewindoworgroup {}", direction),
+            Dispatch::LockActiveGroup(lock) => f.write_str(match lock {
+                GroupLock::Lock => "lockactivegroup lock",
+                GroupLock::Unlock => "lockactivegroup unlock",
+                GroupLock::Toggle => "lockactivegroup toggle",
+            }),
+            Dispatch::Submap(name) => write!(f, "submap {}", name),
+            Dispatch::Custom { dispatcher, args } if args.is_empty() => f.write_str(dispatcher),
+            Dispatch::Custom { dispatcher, args } => write!(f, "{} {}", dispatcher, args),
+        }
+    }
+}
+
+/// Separates the replies to a `[[BATCH]]` request
+const BATCH_DELIMITER: &str = "\n\n\n";
+
+/// The `[[BATCH]]` request running every dispatch in `dispatches`
+pub(crate) fn batch_request(dispatches: &[Dispatch]) -> Result<String, HyprlandError> {
+    let commands = dispatches
+        .iter()
+        .map(|dispatch| {
+            let command = dispatch.to_string();
+            // Hyprland splits batches on `;`, except inside exec rule brackets
+            if splits_batch(&command) {
+                return Err(HyprlandError::Dispatch {
+                    command,
+                    reply: "`;` can't be used in a batch".to_string(),
+                });
+            }
+            Ok(format!("dispatch {}", command))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(format!("[[BATCH]]{}", commands.join(";")))
+}
+
+fn splits_batch(command: &str) -> bool {
+    let mut depth = 0usize;
+    command.chars().any(|c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ';' => return depth == 0,
+            _ => {}
+        }
+        false
+    })
+}
+
+/// Check Hyprland's replies to `dispatches`, one `ok` or error message each
+pub(crate) fn check_replies(dispatches: &[Dispatch], reply: &str) -> Result<(), HyprlandError> {
+    let replies: Vec<&str> = reply.split(BATCH_DELIMITER).map(str::trim).collect();
+    if replies.len() != dispatches.len() {
+        // A short reply means Hyprland stopped partway, so blame the first
+        // dispatch it didn't answer
+        let command = match dispatches.get(replies.len()) {
+            Some(dispatch) => dispatch.to_string(),
+            None => dispatches.iter().map(Dispatch::to_string).collect::<Vec<_>>().join(";"),
+        };
+        return Err(HyprlandError::Dispatch {
+            command,
+            reply: format!("expected {} replies, got {}: {}", dispatches.len(), replies.len(), reply.trim()),
+        });
+    }
+    match dispatches.iter().zip(replies).find(|(_, reply)| *reply != "ok") {
+        Some((dispatch, reply)) => Err(HyprlandError::Dispatch {
+            command: dispatch.to_string(),
+            reply: reply.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_checks_dispatches() {
+        let dispatches = [
+            Dispatch::Workspace(WorkspaceSelector::MonitorRelative(-1)),
+            Dispatch::MoveToWorkspaceSilent {
+                workspace: WorkspaceSelector::Special(Some("scratch".into())),
+                window: Some(WindowSelector::Address("0x5640a2b0".into())),
+            },
+            Dispatch::Exec {
+                command: "kitty".into(),
+                rules: vec!["workspace 2 silent".into(), "float".into()],
+            },
+            Dispatch::ToggleFloating(None),
+        ];
+        let request = batch_request(&dispatches).unwrap();
+        assert_eq!(
+            request,
+            "[[BATCH]]dispatch workspace m-1;\
+             dispatch movetoworkspacesilent special:scratch,address:0x5640a2b0;\
+             dispatch exec [workspace 2 silent;float] kitty;\
+             dispatch togglefloating"
+        );
+
+        let exec = Dispatch::ExecRaw("a; b".into());
+        assert!(matches!(batch_request(&[exec]), Err(HyprlandError::Dispatch { .. })));
+
+        assert!(check_replies(&dispatches, "ok\n\n\nok\n\n\nok\n\n\nok").is_ok());
+        assert!(check_replies(&dispatches[..1], "ok").is_ok());
+        match check_replies(&dispatches, "ok\n\n\nInvalid dispatcher\n\n\nok\n\n\nok") {
+            Err(HyprlandError::Dispatch { command, reply }) => {
+                assert!(command.starts_with("movetoworkspacesilent"));
+                assert_eq!(reply, "Invalid dispatcher");
+            }
+            other => panic!("{:?}", other),
+        }
+        match check_replies(&dispatches, "ok") {
+            Err(HyprlandError::Dispatch { command, reply }) => {
+                assert!(command.starts_with("movetoworkspacesilent"));
+                assert_eq!(reply, "expected 4 replies, got 1: ok");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(check_replies(&dispatches[..1], "ok\n\n\nok").is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! Hyprland event socket
+//!
+//! Hyprland broadcasts `EVENT>>DATA` lines on `.socket2.sock`. [`EventStream`]
+//! reads them into typed [`Event`]s and reconnects on its own when the socket
+//! goes away. A restarted Hyprland gets a new instance signature, so the
+//! stream then follows the newest instance directory next to the old one.
+//!
+//! Window addresses are given `0x`-prefixed, the way the JSON queries return
+//! them, so they can be compared with [`crate::Window::address`].
+
+use crate::HyprlandError;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UnixStream;
+
+/// First delay before reconnecting, doubled on every failed attempt
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Upper bound for the reconnect delay
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// An event from Hyprland's event socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// `workspace>>NAME`
+    Workspace { name: String },
+    /// `workspacev2>>ID,NAME`
+    WorkspaceV2 { id: i32, name: String },
+    /// `focusedmon>>MONITOR,WORKSPACE`
+    FocusedMonitor { monitor: String, workspace: String },
+    /// `focusedmonv2>>MONITOR,WORKSPACEID`
+    FocusedMonitorV2 { monitor: String, workspace_id: i32 },
+    /// `activewindow>>CLASS,TITLE`
+    ActiveWindow { class: String, title: String },
+    /// `activewindowv2>>ADDRESS`, `None` when nothing is focused
+    ActiveWindowV2 { address: Option<String> },
+    /// `fullscreen>>0|1`
+    Fullscreen(bool),
+    /// `monitoradded>>NAME`
+    MonitorAdded { name: String },
+    /// `monitoraddedv2>>ID,NAME,DESCRIPTION`
+    MonitorAddedV2 {
+        id: i32,
+        name: String,
+        description: String,
+    },
+    /// `monitorremoved>>NAME`
+    MonitorRemoved { name: String },
+    /// `monitorremovedv2>>ID,NAME,DESCRIPTION`
+    MonitorRemovedV2 {
+        id: i32,
+        name: String,
+        description: String,
+    },
+    /// `createworkspace>>NAME`
+    CreateWorkspace { name: String },
+    /// `createworkspacev2>>ID,NAME`
+    CreateWorkspaceV2 { id: i32, name: String },
+    /// `destroyworkspace>>NAME`
+    DestroyWorkspace { name: String },
+    /// `destroyworkspacev2>>ID,NAME`
+    DestroyWorkspaceV2 { id: i32, name: String },
+    /// `moveworkspace>>NAME,MONITOR`
+    MoveWorkspace { name: String, monitor: String },
+    /// `moveworkspacev2>>ID,NAME,MONITOR`
+    MoveWorkspaceV2 {
+        id: i32,
+        name: String,
+        monitor: String,
+    },
+    /// `renameworkspace>>ID,NAME`
+    RenameWorkspace { id: i32, name: String },
+    /// `activespecial>>NAME,MONITOR`, with an empty name when closed
+    ActiveSpecial { name: String, monitor: String },
+    /// `activelayout>>KEYBOARD,LAYOUT`
+    ActiveLayout { keyboard: String, layout: String },
+    /// `openwindow>>ADDRESS,WORKSPACE,CLASS,TITLE`
+    OpenWindow {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+    /// `closewindow>>ADDRESS`
+    CloseWindow { address: String },
+    /// `movewindow>>ADDRESS,WORKSPACE`
+    MoveWindow { address: String, workspace: String },
+    /// `movewindowv2>>ADDRESS,WORKSPACEID,WORKSPACE`
+    MoveWindowV2 {
+        address: String,
+        workspace_id: i32,
+        workspace: String,
+    },
+    /// `windowtitle>>ADDRESS`
+    WindowTitle { address: String },
+    /// `windowtitlev2>>ADDRESS,TITLE`
+    WindowTitleV2 { address: String, title: String },
+    /// `changefloatingmode>>ADDRESS,0|1`
+    ChangeFloatingMode { address: String, floating: bool },
+    /// `pin>>ADDRESS,0|1`
+    Pin { address: String, pinned: bool },
+    /// `urgent>>ADDRESS`
+    Urgent { address: String },
+    /// `openlayer>>NAMESPACE`
+    OpenLayer { namespace: String },
+    /// `closelayer>>NAMESPACE`
+    CloseLayer { namespace: String },
+    /// `submap>>NAME`, with an empty name for the default one
+    Submap { name: String },
+    /// `configreloaded>>`
+    ConfigReloaded,
+    /// Anything else, including known events whose data didn't parse
+    Unknown { name: String, data: String },
+}
+
+impl Event {
+    /// Parse one `EVENT>>DATA` line
+    pub fn parse(line: &str) -> Self {
+        let (name, data) = line.split_once(">>").unwrap_or((line, ""));
+        Self::parse_known(name, data).unwrap_or_else(|| Event::Unknown {
+            name: name.to_string(),
+            data: data.to_string(),
+        })
+    }
+
+    fn parse_known(name: &str, data: &str) -> Option<Self> {
+        let id = |s: &str| s.parse::<i32>().ok();
+        let flag = |s: &str| match s {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None,
+        };
+
+        let event = match name {
+            "workspace" => Event::Workspace { name: data.to_string() },
+            "workspacev2" => {
+                let [id_, name] = fields::<2>(data)?;
+                Event::WorkspaceV2 { id: id(&id_)?, name }
+            }
+            "focusedmon" => {
+                let [monitor, workspace] = fields::<2>(data)?;
+                Event::FocusedMonitor { monitor, workspace }
+            }
+            "focusedmonv2" => {
+                let [monitor, workspace_id] = fields::<2>(data)?;
+                Event::FocusedMonitorV2 {
+                    monitor,
+                    workspace_id: id(&workspace_id)?,
+                }
+            }
+            "activewindow" => {
+                let [class, title] = fields::<2>(data)?;
+                Event::ActiveWindow { class, title }
+            }
+            "activewindowv2" => Event::ActiveWindowV2 {
+                address: match data {
+                    "" | "," => None,
+                    address => Some(window_address(address)),
+                },
+            },
+            "fullscreen" => Event::Fullscreen(flag(data)?),
+            "monitoradded" => Event::MonitorAdded { name: data.to_string() },
+            "monitoraddedv2" => {
+                let [id_, name, description] = fields::<3>(data)?;
+                Event::MonitorAddedV2 {
+                    id: id(&id_)?,
+                    name,
+                    description,
+                }
+            }
+            "monitorremoved" => Event::MonitorRemoved { name: data.to_string() },
+            "monitorremovedv2" => {
+                let [id_, name, description] = fields::<3>(data)?;
+                Event::MonitorRemovedV2 {
+                    id: id(&id_)?,
+                    name,
+                    description,
+                }
+            }
+            "createworkspace" => Event::CreateWorkspace { name: data.to_string() },
+            "createworkspacev2" => {
+                let [id_, name] = fields::<2>(data)?;
+                Event::CreateWorkspaceV2 { id: id(&id_)?, name }
+            }
+            "destroyworkspace" => Event::DestroyWorkspace { name: data.to_string() },
+            "destroyworkspacev2" => {
+                let [id_, name] = fields::<2>(data)?;
+                Event::DestroyWorkspaceV2 { id: id(&id_)?, name }
+            }
+            "moveworkspace" => {
+                // Workspace names may contain commas, monitor names don't
+                let (name, monitor) = data.rsplit_once(',')?;
+                Event::MoveWorkspace {
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "moveworkspacev2" => {
+                let (id_, rest) = data.split_once(',')?;
+                let (name, monitor) = rest.rsplit_once(',')?;
+                Event::MoveWorkspaceV2 {
+                    id: id(id_)?,
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "renameworkspace" => {
+                let [id_, name] = fields::<2>(data)?;
+                Event::RenameWorkspace { id: id(&id_)?, name }
+            }
+            "activespecial" => {
+                let (name, monitor) = data.rsplit_once(',')?;
+                Event::ActiveSpecial {
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "activelayout" => {
+                // Keyboard device names don't contain commas, layout names may
+                let [keyboard, layout] = fields::<2>(data)?;
+                Event::ActiveLayout { keyboard, layout }
+            }
+            "openwindow" => {
+                let [address, workspace, class, title] = fields::<4>(data)?;
+                Event::OpenWindow {
+                    address: window_address(&address),
+                    workspace,
+                    class,
+                    title,
+                }
+            }
+            "closewindow" => Event::CloseWindow {
+                address: window_address(data),
+            },
+            "movewindow" => {
+                let [address, workspace] = fields::<2>(data)?;
+                Event::MoveWindow {
+                    address: window_address(&address),
+                    workspace,
+                }
+            }
+            "movewindowv2" => {
+                let [address, workspace_id, workspace] = fields::<3>(data)?;
+                Event::MoveWindowV2 {
+                    address: window_address(&address),
+                    workspace_id: id(&workspace_id)?,
+                    workspace,
+                }
+            }
+            "windowtitle" => Event::WindowTitle {
+                address: window_address(data),
+            },
+            "windowtitlev2" => {
+                let [address, title] = fields::<2>(data)?;
+                Event::WindowTitleV2 {
+                    address: window_address(&address),
+                    title,
+                }
+            }
+            "changefloatingmode" => {
+                let [address, floating] = fields::<2>(data)?;
+                Event::ChangeFloatingMode {
+                    address: window_address(&address),
+                    floating: flag(&floating)?,
+                }
+            }
+            "pin" => {
+                let [address, pinned] = fields::<2>(data)?;
+                Event::Pin {
+                    address: window_address(&address),
+                    pinned: flag(&pinned)?,
+                }
+            }
+            "urgent" => Event::Urgent {
+                address: window_address(data),
+            },
+            "openlayer" => Event::OpenLayer { namespace: data.to_string() },
+            "closelayer" => Event::CloseLayer { namespace: data.to_string() },
+            "submap" => Event::Submap { name: data.to_string() },
+            "configreloaded" => Event::ConfigReloaded,
+            _ => return None,
+        };
+
+        Some(event)
+    }
+}
+
+/// Split `data` into exactly `N` comma-separated fields, the last of which
+/// keeps any further commas (titles, workspace names)
+fn fields<const N: usize>(data: &str) -> Option<[String; N]> {
+    let fields: Vec<String> = data.splitn(N, ',').map(str::to_string).collect();
+    fields.try_into().ok()
+}
+
+/// Events carry bare hex addresses, the JSON queries `0x`-prefixed ones
+fn window_address(address: &str) -> String {
+    if address.starts_with("0x") {
+        address.to_string()
+    } else {
+        format!("0x{}", address)
+    }
+}
+
+/// Connection to `.socket2.sock` yielding [`Event`]s
+///
+/// Reconnects with backoff whenever the socket closes or fails; events sent
+/// while disconnected are lost.
+pub struct EventStream {
+    socket_path: PathBuf,
+    reader: Option<BufReader<UnixStream>>,
+    line: Vec<u8>,
+}
+
+impl EventStream {
+    /// Connect to the event socket at `socket_path`
+    pub async fn connect(socket_path: impl AsRef<Path>) -> Result<Self, HyprlandError> {
+        let socket_path = socket_path.as_ref().to_path_buf();
+        let stream = UnixStream::connect(&socket_path)
+            .await
+            .map_err(HyprlandError::Connect)?;
+
+        Ok(Self {
+            socket_path,
+            reader: Some(BufReader::new(stream)),
+            line: Vec::new(),
+        })
+    }
+
+    /// Wait for the next event, reconnecting as needed.
+    ///
+    /// Cancel safe: a partially read line is kept for the next call, so this
+    /// can be used in `tokio::select!`.
+    pub async fn next(&mut self) -> Event {
+        loop {
+            let Some(reader) = &mut self.reader else {
+                self.reconnect().await;
+                continue;
+            };
+
+            match reader.read_until(b'\n', &mut self.line).await {
+                Ok(0) => {
+                    tracing::warn!("Hyprland event socket closed, reconnecting");
+                    self.reader = None;
+                }
+                Ok(_) if self.line.last() == Some(&b'\n') => {
+                    let line = String::from_utf8_lossy(&self.line[..self.line.len() - 1]).into_owned();
+                    self.line.clear();
+                    if !line.is_empty() {
+                        return Event::parse(&line);
+                    }
+                }
+                // A line cut short by EOF; the next read reports the close
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to read Hyprland event socket: {}, reconnecting", e);
+                    self.reader = None;
+                }
+            }
+        }
+    }
+
+    async fn reconnect(&mut self) {
+        self.line.clear();
+        let mut delay = RECONNECT_DELAY;
+        loop {
+            tokio::time::sleep(delay).await;
+            let error = match UnixStream::connect(&self.socket_path).await {
+                Ok(stream) => {
+                    tracing::info!("Reconnected to Hyprland event socket");
+                    self.reader = Some(BufReader::new(stream));
+                    return;
+                }
+                Err(e) => e,
+            };
+            tracing::debug!("Reconnecting to {} failed: {}", self.socket_path.display(), error);
+
+            if let Some(path) = self.newest_socket().filter(|path| *path != self.socket_path) {
+                if let Ok(stream) = UnixStream::connect(&path).await {
+                    tracing::info!("Hyprland restarted, following it to {}", path.display());
+                    self.socket_path = path;
+                    self.reader = Some(BufReader::new(stream));
+                    return;
+                }
+            }
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// The event socket of the most recently started instance in the
+    /// directory holding ours (`$XDG_RUNTIME_DIR/hypr`)
+    fn newest_socket(&self) -> Option<PathBuf> {
+        let hypr_dir = self.socket_path.parent()?.parent()?;
+        let file_name = self.socket_path.file_name()?;
+        std::fs::read_dir(hypr_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join(file_name))
+            .filter_map(|path| Some((path.symlink_metadata().ok()?.modified().ok()?, path)))
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, path)| path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixListener;
+
+    #[test]
+    fn parses_events() {
+        let cases = [
+            ("workspace>>2", Event::Workspace { name: "2".into() }),
+            ("workspacev2>>-98,special:scratch", Event::WorkspaceV2 { id: -98, name: "special:scratch".into() }),
+            ("activewindowv2>>5640a2b0", Event::ActiveWindowV2 { address: Some("0x5640a2b0".into()) }),
+            ("activewindowv2>>", Event::ActiveWindowV2 { address: None }),
+            (
+                "openwindow>>5640a2b0,1,kitty,vim: a, b",
+                Event::OpenWindow {
+                    address: "0x5640a2b0".into(),
+                    workspace: "1".into(),
+                    class: "kitty".into(),
+                    title: "vim: a, b".into(),
+                },
+            ),
+            ("movewindowv2>>5640a2b0,3,3", Event::MoveWindowV2 { address: "0x5640a2b0".into(), workspace_id: 3, workspace: "3".into() }),
+            (
+                "monitoraddedv2>>1,DP-1,Dell Inc. U2720Q",
+                Event::MonitorAddedV2 { id: 1, name: "DP-1".into(), description: "Dell Inc. U2720Q".into() },
+            ),
+            ("submap>>", Event::Submap { name: String::new() }),
+            ("fullscreen>>1", Event::Fullscreen(true)),
+            ("configreloaded>>", Event::ConfigReloaded),
+            ("fullscreen>>maybe", Event::Unknown { name: "fullscreen".into(), data: "maybe".into() }),
+            ("bell>>a,b", Event::Unknown { name: "bell".into(), data: "a,b".into() }),
+        ];
+
+        for (line, event) in cases {
+            assert_eq!(Event::parse(line), event, "{}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_close() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".socket2.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            conn.write_all(b"workspace>>1\nurgent>>ab").await.unwrap();
+            conn.write_all(b"cd\n").await.unwrap();
+            drop(conn);
+
+            let (mut conn, _) = listener.accept().await.unwrap();
+            conn.write_all(b"submap>>resize\n").await.unwrap();
+            conn
+        });
+
+        let mut events = EventStream::connect(&path).await.unwrap();
+        assert_eq!(events.next().await, Event::Workspace { name: "1".into() });
+        assert_eq!(events.next().await, Event::Urgent { address: "0xabcd".into() });
+        assert_eq!(events.next().await, Event::Submap { name: "resize".into() });
+
+        drop(server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn follows_restarted_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("hypr/old-signature/.socket2.sock");
+        std::fs::create_dir_all(old.parent().unwrap()).unwrap();
+        let listener = UnixListener::bind(&old).unwrap();
+
+        let mut events = EventStream::connect(&old).await.unwrap();
+        let (mut conn, _) = listener.accept().await.unwrap();
+        conn.write_all(b"workspace>>1\n").await.unwrap();
+        assert_eq!(events.next().await, Event::Workspace { name: "1".into() });
+
+        // Hyprland exits and comes back under a new signature
+        drop((conn, listener));
+        std::fs::remove_dir_all(old.parent().unwrap()).unwrap();
+        let new = dir.path().join("hypr/new-signature/.socket2.sock");
+        std::fs::create_dir_all(new.parent().unwrap()).unwrap();
+        let listener = UnixListener::bind(&new).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            conn.write_all(b"configreloaded>>\n").await.unwrap();
+            conn
+        });
+
+        assert_eq!(events.next().await, Event::ConfigReloaded);
+        drop(server.await.unwrap());
+    }
+}
@@ -1,8 +1,17 @@
 //! Hyprland IPC communication
 //!
-//! Provides typed access to Hyprland socket commands.
+//! Provides typed access to Hyprland socket commands and the event stream.
 
-use serde::{Deserialize, Serialize};
+pub mod dispatch;
+pub mod events;
+pub mod types;
+
+pub use dispatch::{Direction, Dispatch, FullscreenMode, GroupLock, WindowSelector, WorkspaceSelector};
+pub use events::{Event, EventStream};
+pub use types::*;
+
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::UnixStream;
@@ -10,6 +19,7 @@ use tokio::net::UnixStream;
 /// Hyprland client for IPC communication
 pub struct HyprlandClient {
     socket_path: PathBuf,
+    event_socket_path: PathBuf,
 }
 
 impl HyprlandClient {
@@ -21,103 +31,165 @@ impl HyprlandClient {
         let xdg_runtime = std::env::var("XDG_RUNTIME_DIR")
             .unwrap_or_else(|_| "/run/user/1000".to_string());
 
-        let socket_path = PathBuf::from(xdg_runtime)
-            .join("hypr")
-            .join(&his)
-            .join(".socket.sock");
+        let instance_dir = PathBuf::from(xdg_runtime).join("hypr").join(&his);
 
-        Ok(Self { socket_path })
+        Ok(Self {
+            socket_path: instance_dir.join(".socket.sock"),
+            event_socket_path: instance_dir.join(".socket2.sock"),
+        })
+    }
+
+    /// Connect to the event socket.
+    ///
+    /// Fails if it can't connect now; once connected, the stream reconnects
+    /// on its own.
+    pub async fn events(&self) -> Result<EventStream, HyprlandError> {
+        EventStream::connect(&self.event_socket_path).await
     }
 
     /// Send a command and get JSON response
     pub async fn command(&self, cmd: &str) -> Result<String, HyprlandError> {
-        let mut stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(|e| HyprlandError::Connect(e))?;
-
-        stream
-            .write_all(format!("j/{}", cmd).as_bytes())
-            .await
-            .map_err(|e| HyprlandError::Write(e))?;
+        self.request(&format!("j/{}", cmd)).await
+    }
 
-        let mut response = String::new();
-        stream
-            .read_to_string(&mut response)
-            .await
-            .map_err(|e| HyprlandError::Read(e))?;
+    /// Send a command and parse its JSON response
+    pub async fn query<T: DeserializeOwned>(&self, cmd: &str) -> Result<T, HyprlandError> {
+        let response = self.command(cmd).await?;
+        serde_json::from_str(&response).map_err(HyprlandError::Parse)
+    }
 
-        Ok(response)
+    /// Get active window info, `None` when no window is focused
+    pub async fn active_window(&self) -> Result<Option<Window>, HyprlandError> {
+        // Hyprland answers `{}` when nothing is focused
+        let window: serde_json::Map<String, serde_json::Value> = self.query("activewindow").await?;
+        if window.is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_value(window.into())
+            .map(Some)
+            .map_err(HyprlandError::Parse)
     }
 
-    /// Get active window info
-    pub async fn active_window(&self) -> Result<Window, HyprlandError> {
-        let response = self.command("activewindow").await?;
-        serde_json::from_str(&response).map_err(HyprlandError::Parse)
+    /// Get all windows
+    pub async fn clients(&self) -> Result<Vec<Window>, HyprlandError> {
+        self.query("clients").await
     }
 
     /// Get all workspaces
     pub async fn workspaces(&self) -> Result<Vec<Workspace>, HyprlandError> {
-        let response = self.command("workspaces").await?;
-        serde_json::from_str(&response).map_err(HyprlandError::Parse)
+        self.query("workspaces").await
+    }
+
+    /// Get the focused workspace
+    pub async fn active_workspace(&self) -> Result<Workspace, HyprlandError> {
+        self.query("activeworkspace").await
     }
 
-    /// Get all monitors
+    /// Get all enabled monitors
     pub async fn monitors(&self) -> Result<Vec<Monitor>, HyprlandError> {
-        let response = self.command("monitors").await?;
-        serde_json::from_str(&response).map_err(HyprlandError::Parse)
+        self.query("monitors").await
     }
 
-    /// Dispatch a Hyprland command
-    pub async fn dispatch(&self, args: &str) -> Result<(), HyprlandError> {
-        let mut stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(|e| HyprlandError::Connect(e))?;
+    /// Get all monitors, disabled ones included
+    pub async fn all_monitors(&self) -> Result<Vec<Monitor>, HyprlandError> {
+        self.query("monitors all").await
+    }
 
-        stream
-            .write_all(format!("/dispatch {}", args).as_bytes())
-            .await
-            .map_err(|e| HyprlandError::Write(e))?;
+    /// Get layer surfaces, keyed by monitor name
+    pub async fn layers(&self) -> Result<BTreeMap<String, MonitorLayers>, HyprlandError> {
+        self.query("layers").await
+    }
 
-        Ok(())
+    /// Get input devices
+    pub async fn devices(&self) -> Result<Devices, HyprlandError> {
+        self.query("devices").await
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Window {
-    pub address: String,
-    pub title: String,
-    pub class: String,
-    pub pid: i32,
-    pub workspace: WorkspaceRef,
-    pub floating: bool,
-    pub fullscreen: i32,
-}
+    /// Get all key and mouse bindings
+    pub async fn binds(&self) -> Result<Vec<Bind>, HyprlandError> {
+        self.query("binds").await
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkspaceRef {
-    pub id: i32,
-    pub name: String,
-}
+    /// Get a config option, e.g. `general:border_size`
+    pub async fn option(&self, name: &str) -> Result<HyprOption, HyprlandError> {
+        self.query(&format!("getoption {}", name)).await
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Workspace {
-    pub id: i32,
-    pub name: String,
-    pub monitor: String,
-    pub windows: i32,
-}
+    /// Get Hyprland's version info
+    pub async fn version(&self) -> Result<Version, HyprlandError> {
+        self.query("version").await
+    }
+
+    /// Get the cursor position
+    pub async fn cursor_pos(&self) -> Result<CursorPos, HyprlandError> {
+        self.query("cursorpos").await
+    }
+
+    /// Get config errors, empty when the config is fine
+    pub async fn config_errors(&self) -> Result<Vec<String>, HyprlandError> {
+        // Hyprland answers `[""]` when there are none
+        let errors: Vec<String> = self.query("configerrors").await?;
+        Ok(errors.into_iter().filter(|e| !e.is_empty()).collect())
+    }
+
+    /// Get animations and bezier curves
+    pub async fn animations(&self) -> Result<Animations, HyprlandError> {
+        self.query("animations").await
+    }
+
+    /// Get workspace rules
+    pub async fn workspace_rules(&self) -> Result<Vec<WorkspaceRule>, HyprlandError> {
+        self.query("workspacerules").await
+    }
+
+    /// Get shortcuts registered through the global shortcuts portal
+    pub async fn global_shortcuts(&self) -> Result<Vec<GlobalShortcut>, HyprlandError> {
+        self.query("globalshortcuts").await
+    }
+
+    /// Get the decorations of the window matching `window`, e.g.
+    /// `address:0x5640a2b0` or a class regex
+    pub async fn decorations(&self, window: &str) -> Result<Vec<Decoration>, HyprlandError> {
+        self.query(&format!("decorations {}", window)).await
+    }
+
+    /// Run a dispatcher, failing with [`HyprlandError::Dispatch`] if
+    /// Hyprland rejects it
+    pub async fn dispatch(&self, dispatch: &Dispatch) -> Result<(), HyprlandError> {
+        let reply = self.request(&format!("/dispatch {}", dispatch)).await?;
+        dispatch::check_replies(std::slice::from_ref(dispatch), &reply)
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Monitor {
-    pub id: i32,
-    pub name: String,
-    pub width: i32,
-    pub height: i32,
-    pub x: i32,
-    pub y: i32,
-    pub scale: f32,
-    #[serde(rename = "activeWorkspace")]
-    pub active_workspace: WorkspaceRef,
+    /// Run several dispatchers in one request, so Hyprland applies them
+    /// together. Fails with the first one Hyprland rejected; the others
+    /// still ran.
+    pub async fn batch(&self, dispatches: &[Dispatch]) -> Result<(), HyprlandError> {
+        if dispatches.is_empty() {
+            return Ok(());
+        }
+        let reply = self.request(&dispatch::batch_request(dispatches)?).await?;
+        dispatch::check_replies(dispatches, &reply)
+    }
+
+    /// Write a raw request and read the whole reply
+    async fn request(&self, request: &str) -> Result<String, HyprlandError> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(HyprlandError::Connect)?;
+
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(HyprlandError::Write)?;
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .await
+            .map_err(HyprlandError::Read)?;
+
+        Ok(response)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -136,4 +208,7 @@ pub enum HyprlandError {
 
     #[error("Failed to parse response: {0}")]
     Parse(serde_json::Error),
+
+    #[error("Dispatch `{command}` failed: {reply}")]
+    Dispatch { command: String, reply: String },
 }
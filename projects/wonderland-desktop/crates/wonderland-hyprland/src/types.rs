@@ -0,0 +1,458 @@
+//! Query results
+//!
+//! Types for the JSON Hyprland returns from `j/` requests. Fields missing in
+//! older versions fall back to their defaults, and fields this crate doesn't
+//! know yet are kept in each type's `extra` map, so a newer Hyprland doesn't
+//! break parsing.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Fields not covered by a type's own
+pub type Extra = Map<String, Value>;
+
+/// A window, from `clients` and `activewindow`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Window {
+    pub address: String,
+    pub mapped: bool,
+    pub hidden: bool,
+    pub at: [i32; 2],
+    pub size: [i32; 2],
+    pub workspace: WorkspaceRef,
+    pub floating: bool,
+    pub pseudo: bool,
+    pub monitor: i32,
+    pub class: String,
+    pub title: String,
+    #[serde(rename = "initialClass")]
+    pub initial_class: String,
+    #[serde(rename = "initialTitle")]
+    pub initial_title: String,
+    pub pid: i32,
+    pub xwayland: bool,
+    pub pinned: bool,
+    pub fullscreen: i32,
+    #[serde(rename = "fullscreenClient")]
+    pub fullscreen_client: i32,
+    /// Addresses of the windows in this one's group, in order
+    pub grouped: Vec<String>,
+    pub tags: Vec<String>,
+    pub swallowing: String,
+    /// 0 for the focused window, counting up for less recently focused ones
+    #[serde(rename = "focusHistoryID")]
+    pub focus_history_id: i32,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceRef {
+    pub id: i32,
+    pub name: String,
+}
+
+/// A workspace, from `workspaces` and `activeworkspace`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    pub id: i32,
+    pub name: String,
+    pub monitor: String,
+    #[serde(rename = "monitorID")]
+    pub monitor_id: i32,
+    pub windows: i32,
+    #[serde(rename = "hasfullscreen")]
+    pub has_fullscreen: bool,
+    #[serde(rename = "lastwindow")]
+    pub last_window: String,
+    #[serde(rename = "lastwindowtitle")]
+    pub last_window_title: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Monitor {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub make: String,
+    pub model: String,
+    pub serial: String,
+    pub width: i32,
+    pub height: i32,
+    #[serde(rename = "refreshRate")]
+    pub refresh_rate: f32,
+    pub x: i32,
+    pub y: i32,
+    #[serde(rename = "activeWorkspace")]
+    pub active_workspace: WorkspaceRef,
+    #[serde(rename = "specialWorkspace")]
+    pub special_workspace: WorkspaceRef,
+    /// Space reserved by layer surfaces: left, top, right, bottom
+    pub reserved: [i32; 4],
+    pub scale: f32,
+    /// `wl_output` transform, 0-7
+    pub transform: i32,
+    pub focused: bool,
+    #[serde(rename = "dpmsStatus")]
+    pub dpms_status: bool,
+    pub vrr: bool,
+    pub disabled: bool,
+    /// Modes like `2560x1440@143.99Hz`
+    #[serde(rename = "availableModes")]
+    pub available_modes: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The layer surfaces of one monitor, from `layers`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorLayers {
+    /// Layers by level: "0" background, "1" bottom, "2" top, "3" overlay
+    pub levels: BTreeMap<String, Vec<Layer>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layer {
+    pub address: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub namespace: String,
+    pub pid: i32,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Input devices, from `devices`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Devices {
+    pub mice: Vec<Mouse>,
+    pub keyboards: Vec<Keyboard>,
+    pub tablets: Vec<Tablet>,
+    pub touch: Vec<InputDevice>,
+    pub switches: Vec<InputDevice>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputDevice {
+    pub address: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mouse {
+    pub address: String,
+    pub name: String,
+    #[serde(rename = "defaultSpeed")]
+    pub default_speed: f64,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyboard {
+    pub address: String,
+    pub name: String,
+    pub rules: String,
+    pub model: String,
+    pub layout: String,
+    pub variant: String,
+    pub options: String,
+    pub active_keymap: String,
+    #[serde(rename = "capsLock")]
+    pub caps_lock: bool,
+    #[serde(rename = "numLock")]
+    pub num_lock: bool,
+    pub main: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A tablet, tablet pad or tablet tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tablet {
+    pub address: String,
+    pub name: Option<String>,
+    /// `tabletPad` or `tabletTool`, absent for the tablet itself
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(rename = "belongsTo")]
+    pub belongs_to: Option<InputDevice>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A key or mouse binding, from `binds`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bind {
+    pub locked: bool,
+    pub mouse: bool,
+    pub release: bool,
+    pub repeat: bool,
+    #[serde(rename = "longPress")]
+    pub long_press: bool,
+    pub non_consuming: bool,
+    pub has_description: bool,
+    pub modmask: u32,
+    pub submap: String,
+    pub key: String,
+    pub keycode: i32,
+    pub catch_all: bool,
+    pub description: String,
+    pub dispatcher: String,
+    pub arg: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A config option's value, from `getoption`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawOption", into = "RawOption")]
+pub struct HyprOption {
+    pub option: String,
+    /// Whether the config sets it, rather than it being the default
+    pub set: bool,
+    pub value: OptionValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// Types without a plain representation (gradients, gaps), as Hyprland
+    /// prints them
+    Custom(String),
+    Vec2([f64; 2]),
+    /// A value under a key this crate doesn't know
+    Other(Extra),
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawOption {
+    option: String,
+    #[serde(default)]
+    set: bool,
+    #[serde(flatten)]
+    value: Extra,
+}
+
+impl From<RawOption> for HyprOption {
+    fn from(raw: RawOption) -> Self {
+        let get = |key: &str| raw.value.get(key);
+        let value = if let Some(i) = get("int").and_then(Value::as_i64) {
+            OptionValue::Int(i)
+        } else if let Some(f) = get("float").and_then(Value::as_f64) {
+            OptionValue::Float(f)
+        } else if let Some(s) = get("str").and_then(Value::as_str) {
+            OptionValue::Str(s.to_string())
+        } else if let Some(s) = get("custom").and_then(Value::as_str) {
+            OptionValue::Custom(s.to_string())
+        } else if let Some(v) = get("vec2").and_then(|v| serde_json::from_value(v.clone()).ok()) {
+            OptionValue::Vec2(v)
+        } else {
+            OptionValue::Other(raw.value)
+        };
+
+        Self {
+            option: raw.option,
+            set: raw.set,
+            value,
+        }
+    }
+}
+
+impl From<HyprOption> for RawOption {
+    fn from(option: HyprOption) -> Self {
+        let (key, value) = match option.value {
+            OptionValue::Int(i) => ("int", Value::from(i)),
+            OptionValue::Float(f) => ("float", Value::from(f)),
+            OptionValue::Str(s) => ("str", Value::from(s)),
+            OptionValue::Custom(s) => ("custom", Value::from(s)),
+            OptionValue::Vec2(v) => ("vec2", Value::from(v.to_vec())),
+            OptionValue::Other(extra) => {
+                return Self {
+                    option: option.option,
+                    set: option.set,
+                    value: extra,
+                }
+            }
+        };
+
+        Self {
+            option: option.option,
+            set: option.set,
+            value: Extra::from_iter([(key.to_string(), value)]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Version {
+    pub branch: String,
+    pub commit: String,
+    /// Empty on older versions; [`Version::tag`] has it too
+    pub version: String,
+    pub dirty: bool,
+    pub commit_message: String,
+    pub commit_date: String,
+    pub tag: String,
+    pub flags: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Cursor position in the global layout, from `cursorpos`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CursorPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Animations and bezier curves, from `animations`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "(Vec<Animation>, Vec<Bezier>)", into = "(Vec<Animation>, Vec<Bezier>)")]
+pub struct Animations {
+    pub animations: Vec<Animation>,
+    pub beziers: Vec<Bezier>,
+}
+
+impl From<(Vec<Animation>, Vec<Bezier>)> for Animations {
+    fn from((animations, beziers): (Vec<Animation>, Vec<Bezier>)) -> Self {
+        Self { animations, beziers }
+    }
+}
+
+impl From<Animations> for (Vec<Animation>, Vec<Bezier>) {
+    fn from(animations: Animations) -> Self {
+        (animations.animations, animations.beziers)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    pub name: String,
+    pub overridden: bool,
+    pub bezier: String,
+    pub enabled: bool,
+    pub speed: f32,
+    pub style: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bezier {
+    pub name: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A `workspace = ...` rule, from `workspacerules`. Only the selector is
+/// always present.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceRule {
+    #[serde(rename = "workspaceString")]
+    pub workspace: String,
+    pub monitor: Option<String>,
+    pub default: Option<bool>,
+    pub persistent: Option<bool>,
+    #[serde(rename = "defaultName")]
+    pub default_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A shortcut registered through the global shortcuts portal
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlobalShortcut {
+    pub name: String,
+    pub description: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A window decoration, from `decorations`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Decoration {
+    #[serde(rename = "decorationName")]
+    pub name: String,
+    pub priority: i32,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_query_results() {
+        let window: Window = serde_json::from_str(
+            r#"{"address": "0x5640a2b0", "mapped": true, "hidden": false, "at": [10, 40],
+                "size": [1260, 700], "workspace": {"id": 2, "name": "2"}, "floating": false,
+                "class": "kitty", "title": "~", "pid": 4242, "xwayland": false, "pinned": false,
+                "fullscreen": 0, "grouped": ["0x5640a2b0", "0x5640c000"], "focusHistoryID": 1,
+                "contentType": "none"}"#,
+        )
+        .unwrap();
+        assert_eq!(window.size, [1260, 700]);
+        assert_eq!(window.grouped.len(), 2);
+        assert_eq!(window.focus_history_id, 1);
+        assert_eq!(window.extra["contentType"], "none");
+
+        let monitor: Monitor = serde_json::from_str(
+            r#"{"id": 0, "name": "DP-1", "width": 2560, "height": 1440, "refreshRate": 143.996,
+                "x": 0, "y": 0, "activeWorkspace": {"id": 1, "name": "1"}, "scale": 1.0,
+                "transform": 0, "vrr": true, "dpmsStatus": true, "disabled": false,
+                "availableModes": ["2560x1440@143.99Hz", "1920x1080@60.00Hz"]}"#,
+        )
+        .unwrap();
+        assert!(monitor.vrr && monitor.dpms_status && !monitor.disabled);
+        assert_eq!(monitor.available_modes[1], "1920x1080@60.00Hz");
+
+        let option: HyprOption =
+            serde_json::from_str(r#"{"option": "general:border_size", "int": 2, "set": true}"#).unwrap();
+        assert_eq!(option.value, OptionValue::Int(2));
+        let option: HyprOption =
+            serde_json::from_str(r#"{"option": "general:col.active_border", "custom": "ff88c0d0 0deg", "set": false}"#)
+                .unwrap();
+        assert_eq!(option.value, OptionValue::Custom("ff88c0d0 0deg".into()));
+
+        let animations: Animations = serde_json::from_str(
+            r#"[[{"name": "global", "overridden": false, "bezier": "default", "enabled": true,
+                  "speed": 8.0, "style": ""}], [{"name": "default"}, {"name": "easeOut"}]]"#,
+        )
+        .unwrap();
+        assert_eq!(animations.animations[0].name, "global");
+        assert_eq!(animations.beziers.len(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! Contrast auditing
+//!
+//! Checks the foreground/background pairings that the Iced catalog in
+//! [`crate::iced_theme`] renders against WCAG 2.x thresholds, and can nudge
+//! derived colors in lightness until they pass.
+
+use crate::color::Color;
+use crate::loader::{Role, Theme};
+use serde::{Deserialize, Serialize};
+
+/// WCAG conformance level for text contrast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WcagLevel {
+    /// 3:1, large text only
+    AaLarge,
+    /// 4.5:1
+    Aa,
+    /// 7:1
+    Aaa,
+}
+
+impl WcagLevel {
+    /// Minimum contrast ratio for this level
+    pub fn min_ratio(&self) -> f32 {
+        match self {
+            WcagLevel::AaLarge => 3.0,
+            WcagLevel::Aa => 4.5,
+            WcagLevel::Aaa => 7.0,
+        }
+    }
+
+    /// Highest level a contrast ratio satisfies
+    pub fn for_ratio(ratio: f32) -> Option<WcagLevel> {
+        [WcagLevel::Aaa, WcagLevel::Aa, WcagLevel::AaLarge]
+            .into_iter()
+            .find(|level| ratio >= level.min_ratio())
+    }
+}
+
+/// Pairings used by the Iced catalog: (where, text role, backdrop role)
+const PAIRINGS: &[(&str, Role, Role)] = &[
+    ("text on background", Role::Foreground, Role::Background),
+    (
+        "text on surface / secondary button",
+        Role::Foreground,
+        Role::Surface,
+    ),
+    (
+        "muted text on background",
+        Role::TextMuted,
+        Role::Background,
+    ),
+    ("text input placeholder", Role::TextMuted, Role::Surface),
+    (
+        "primary text / text button",
+        Role::Primary,
+        Role::Background,
+    ),
+    ("error text", Role::Error, Role::Background),
+    ("primary button text", Role::Background, Role::Primary),
+    ("danger button text", Role::Background, Role::Error),
+    ("success button text", Role::Background, Role::Success),
+];
+
+/// Result of checking one foreground/background pairing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastCheck {
+    /// Which widget or style uses this pairing
+    pub context: String,
+    pub foreground: Role,
+    pub background: Role,
+    pub ratio: f32,
+    /// Highest level reached, `None` if below 3:1
+    pub level: Option<WcagLevel>,
+}
+
+impl ContrastCheck {
+    pub fn passes(&self, level: WcagLevel) -> bool {
+        self.level.is_some_and(|reached| reached >= level)
+    }
+}
+
+/// Contrast report for a whole theme
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastReport {
+    pub checks: Vec<ContrastCheck>,
+}
+
+impl ContrastReport {
+    /// Whether every pairing meets `level`
+    pub fn passes(&self, level: WcagLevel) -> bool {
+        self.checks.iter().all(|check| check.passes(level))
+    }
+
+    /// Pairings that fall short of `level`
+    pub fn failures(&self, level: WcagLevel) -> impl Iterator<Item = &ContrastCheck> {
+        self.checks.iter().filter(move |check| !check.passes(level))
+    }
+}
+
+/// A color changed by [`Theme::correct_contrast`]
+#[derive(Debug, Clone, Copy)]
+pub struct Correction {
+    pub role: Role,
+    pub from: Color,
+    pub to: Color,
+}
+
+impl Theme {
+    /// Check every pairing the Iced catalog renders
+    pub fn audit(&self) -> ContrastReport {
+        let checks = PAIRINGS
+            .iter()
+            .map(|&(context, fg, bg)| {
+                let ratio = self.pair_ratio(fg, bg);
+                ContrastCheck {
+                    context: context.to_string(),
+                    foreground: fg,
+                    background: bg,
+                    ratio,
+                    level: WcagLevel::for_ratio(ratio),
+                }
+            })
+            .collect();
+
+        ContrastReport { checks }
+    }
+
+    /// Adjust the lightness of roles not in `locked` until every pairing
+    /// meets `level`, or the color can't move any further.
+    pub fn correct_contrast(&mut self, level: WcagLevel, locked: &[Role]) -> Vec<Correction> {
+        let original = self.clone();
+
+        // Moving one role can break another pairing, so repeat a few times
+        for _ in 0..4 {
+            let mut changed = false;
+
+            for &(_, fg, bg) in PAIRINGS {
+                if self.pair_ratio(fg, bg) >= level.min_ratio() {
+                    continue;
+                }
+                // Prefer moving the text; fall back to the backdrop
+                let (role, against) = if !locked.contains(&fg) {
+                    (fg, bg)
+                } else if !locked.contains(&bg) {
+                    (bg, fg)
+                } else {
+                    continue;
+                };
+                changed |= self.nudge(role, against, fg, bg, level.min_ratio());
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        Role::ALL
+            .into_iter()
+            .filter(|&role| original.color(role) != self.color(role))
+            .map(|role| Correction {
+                role,
+                from: original.color(role),
+                to: self.color(role),
+            })
+            .collect()
+    }
+
+    /// Step `role` away from `against` in OKLab lightness until `fg` on `bg`
+    /// reaches `target`. Returns whether the color changed.
+    fn nudge(&mut self, role: Role, against: Role, fg: Role, bg: Role, target: f32) -> bool {
+        let start = self.color(role);
+        let mut lch = start.to_oklch();
+        let step = if self.color(against).is_dark() {
+            0.01
+        } else {
+            -0.01
+        };
+
+        while self.pair_ratio(fg, bg) < target {
+            lch.l += step;
+            if !(0.0..=1.0).contains(&lch.l) {
+                break;
+            }
+            *self.color_mut(role) = Color::from_oklch(lch, start.a);
+        }
+
+        self.color(role) != start
+    }
+
+    /// Contrast of `fg` drawn on `bg`, with translucent colors composited
+    /// over the theme background
+    fn pair_ratio(&self, fg: Role, bg: Role) -> f32 {
+        let base = self.background.with_alpha(1.0);
+        let backdrop = if bg == Role::Background {
+            base
+        } else {
+            self.color(bg).over(&base)
+        };
+        self.color(fg).over(&backdrop).contrast_ratio(&backdrop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_palette_is_readable() {
+        let theme = Theme::derive_extended_colors(
+            Color::from_hex("#cdd6f4").unwrap(),
+            Color::from_hex("#1e1e2e").unwrap(),
+        );
+        let report = theme.audit();
+        assert!(
+            report.passes(WcagLevel::Aa),
+            "{:#?}",
+            report.failures(WcagLevel::Aa).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn checks_status_buttons() {
+        let mut theme = Theme::derive_extended_colors(
+            Color::from_hex("#c0caf5").unwrap(),
+            Color::from_hex("#1a1b26").unwrap(),
+        );
+        theme.success = Color::from_hex("#2a3a2a").unwrap();
+
+        let failures: Vec<_> = theme.audit().failures(WcagLevel::Aa).map(|check| check.context.clone()).collect();
+        assert_eq!(failures, ["success button text"]);
+    }
+
+    #[test]
+    fn corrects_unlocked_roles_only() {
+        let mut theme = Theme::derive_extended_colors(
+            Color::from_hex("#c0caf5").unwrap(),
+            Color::from_hex("#1a1b26").unwrap(),
+        );
+        theme.text_muted = Color::from_hex("#3b4261").unwrap();
+        theme.primary = Color::from_hex("#2a2b36").unwrap();
+
+        let fixes = theme.correct_contrast(
+            WcagLevel::Aa,
+            &[Role::Foreground, Role::Background, Role::Primary],
+        );
+
+        assert!(fixes.iter().any(|fix| fix.role == Role::TextMuted));
+        assert!(fixes.iter().all(|fix| fix.role != Role::Primary));
+        assert!(theme.audit().checks.iter().all(|check| {
+            check.passes(WcagLevel::Aa)
+                || check.foreground == Role::Primary
+                || check.background == Role::Primary
+        }));
+    }
+}
@@ -0,0 +1,106 @@
+//! Built-in themes
+//!
+//! A few well-known themes are compiled into the crate so apps have
+//! something to show without `~/.config/theme`: on a fresh machine, in CI,
+//! or in a VM. They are plain theme.toml files under `themes/`.
+
+use crate::loader::{Theme, ThemeError};
+use crate::terminal::TerminalPalette;
+use crate::theme_file::{ThemeFile, Variant};
+use std::path::{Path, PathBuf};
+
+/// Built-in theme.toml files, keyed by theme name
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("catppuccin", include_str!("../themes/catppuccin/theme.toml")),
+    ("catppuccin-latte", include_str!("../themes/catppuccin-latte/theme.toml")),
+    ("gruvbox", include_str!("../themes/gruvbox/theme.toml")),
+    ("nord", include_str!("../themes/nord/theme.toml")),
+    ("rose-pine", include_str!("../themes/rose-pine/theme.toml")),
+    ("tokyo-night", include_str!("../themes/tokyo-night/theme.toml")),
+];
+
+/// The theme apps fall back to when nothing else can be loaded
+pub const DEFAULT_BUILTIN: &str = "catppuccin";
+
+/// Names of the built-in themes
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    BUILTIN_THEMES.iter().map(|(name, _)| *name)
+}
+
+/// A built-in theme by name. It has no directory, so `path` is empty.
+pub fn builtin_theme(name: &str) -> Option<Theme> {
+    load(name, None)
+}
+
+/// A built-in theme with a variant table applied
+pub(crate) fn load(name: &str, variant: Option<Variant>) -> Option<Theme> {
+    let (name, source) = BUILTIN_THEMES.iter().find(|(builtin, _)| *builtin == name)?;
+    // Every built-in is checked by the tests below
+    Some(parse(name, source, variant).expect("invalid built-in theme"))
+}
+
+/// theme.toml content of a built-in theme, for `extends`
+pub(crate) fn source(name: &str) -> Option<(String, PathBuf)> {
+    BUILTIN_THEMES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(name, source)| (source.to_string(), source_path(name)))
+}
+
+/// The fallback theme, [`DEFAULT_BUILTIN`]
+pub fn default_theme() -> Theme {
+    builtin_theme(DEFAULT_BUILTIN).expect("default built-in theme is missing")
+}
+
+/// Pseudo path built-in themes are reported under in errors
+fn source_path(name: &str) -> PathBuf {
+    Path::new("builtin").join(name).join("theme.toml")
+}
+
+fn parse(name: &str, source: &str, variant: Option<Variant>) -> Result<Theme, ThemeError> {
+    let path = source_path(name);
+    let file = ThemeFile::resolve(name, source, &path, variant, &|_: &str, parent: &str| Ok(self::source(parent)))?;
+
+    let (foreground, background) = match file.base_colors(&path)? {
+        (Some(fg), Some(bg)) => (fg, bg),
+        (None, _) => return Err(ThemeError::MissingColor("foreground".to_string())),
+        (_, None) => return Err(ThemeError::MissingColor("background".to_string())),
+    };
+
+    let mut theme = Theme::derive_extended_colors(foreground, background);
+    theme.name = name.to_string();
+    theme.path = PathBuf::new();
+    theme.meta.display_name = name.to_string();
+    file.apply(&mut theme, &path)?;
+    theme.terminal = TerminalPalette::derive(&theme);
+    file.apply_terminal(&mut theme.terminal, &path)?;
+
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::WcagLevel;
+
+    #[test]
+    fn builtins_are_valid() {
+        for (name, source) in BUILTIN_THEMES {
+            for variant in [None, Some(Variant::Dark), Some(Variant::Light)] {
+                let theme = parse(name, source, variant).unwrap_or_else(|e| panic!("{}: {}", name, e));
+                assert!(theme.is_builtin());
+                let report = theme.audit();
+                assert!(
+                    report.passes(WcagLevel::Aa),
+                    "{} {:?} fails AA: {:#?}",
+                    name,
+                    variant,
+                    report.failures(WcagLevel::Aa).collect::<Vec<_>>()
+                );
+            }
+        }
+
+        assert_eq!(default_theme().background.to_hex(), "#1e1e2e");
+        assert!(builtin_theme("no-such-theme").is_none());
+    }
+}
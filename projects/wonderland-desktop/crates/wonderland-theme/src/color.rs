@@ -1,9 +1,18 @@
 //! Color parsing and conversion utilities
+//!
+//! Colors are stored as non-linear sRGB. Perceptual operations (lighten,
+//! darken, mix) work in OKLab; contrast follows WCAG 2.x.
 
-use serde::{Deserialize, Serialize};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
 /// RGBA color representation
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+///
+/// Serializes as a hex string (`"#rrggbb"` or `"#rrggbbaa"`) and deserializes
+/// from any CSS color string.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -11,7 +20,42 @@ pub struct Color {
     pub a: f32,
 }
 
+/// Hue (degrees), saturation and lightness, all but hue in 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// Hue (degrees), saturation and value, all but hue in 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+/// OKLab perceptual color space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// OKLab in polar form: lightness, chroma and hue (degrees)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
 impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
     pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
@@ -19,6 +63,10 @@ impl Color {
     /// Parse hex color string (#RGB, #RGBA, #RRGGBB, #RRGGBBAA)
     pub fn from_hex(hex: &str) -> Result<Self, ColorError> {
         let hex = hex.trim_start_matches('#');
+        // Checked up front, as the slicing below is by byte
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidDigit(hex.to_string()));
+        }
 
         let (r, g, b, a) = match hex.len() {
             3 => {
@@ -59,34 +107,274 @@ impl Color {
     }
 
     /// Convert to Iced color
+    #[cfg(feature = "iced")]
     pub fn to_iced(&self) -> iced::Color {
         iced::Color::from_rgba(self.r, self.g, self.b, self.a)
     }
 
-    /// Lighten the color by a factor (0.0 - 1.0)
-    pub fn lighten(&self, factor: f32) -> Self {
-        Self {
-            r: (self.r + (1.0 - self.r) * factor).min(1.0),
-            g: (self.g + (1.0 - self.g) * factor).min(1.0),
-            b: (self.b + (1.0 - self.b) * factor).min(1.0),
-            a: self.a,
+    /// Channels as 8-bit values
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// Format as `#rrggbb`, or `#rrggbbaa` when not fully opaque
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
         }
     }
 
-    /// Darken the color by a factor (0.0 - 1.0)
-    pub fn darken(&self, factor: f32) -> Self {
-        Self {
-            r: (self.r * (1.0 - factor)).max(0.0),
-            g: (self.g * (1.0 - factor)).max(0.0),
-            b: (self.b * (1.0 - factor)).max(0.0),
-            a: self.a,
+    /// Format as CSS `rgb()`/`rgba()`
+    pub fn to_css(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        if a == 255 {
+            format!("rgb({}, {}, {})", r, g, b)
+        } else {
+            format!(
+                "rgba({}, {}, {}, {})",
+                r,
+                g,
+                b,
+                (self.a * 1000.0).round() / 1000.0
+            )
         }
     }
 
+    /// Lighten the color by a factor (0.0 - 1.0), moving OKLab lightness toward white
+    pub fn lighten(&self, factor: f32) -> Self {
+        let mut lab = self.to_oklab();
+        lab.l += (1.0 - lab.l) * factor;
+        Self::from_oklab(lab, self.a)
+    }
+
+    /// Darken the color by a factor (0.0 - 1.0), moving OKLab lightness toward black
+    pub fn darken(&self, factor: f32) -> Self {
+        let mut lab = self.to_oklab();
+        lab.l *= 1.0 - factor;
+        Self::from_oklab(lab, self.a)
+    }
+
     /// Set alpha value
     pub fn with_alpha(&self, a: f32) -> Self {
         Self { a, ..*self }
     }
+
+    /// Straight interpolation of the sRGB channels (t = 0.0 gives `self`)
+    pub fn lerp(&self, other: &Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    /// Perceptual mix in OKLab (t = 0.0 gives `self`)
+    pub fn mix(&self, other: &Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let (a, b) = (self.to_oklab(), other.to_oklab());
+        Self::from_oklab(
+            Oklab {
+                l: a.l + (b.l - a.l) * t,
+                a: a.a + (b.a - a.a) * t,
+                b: a.b + (b.b - a.b) * t,
+            },
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Alpha-composite this color over an opaque backdrop
+    pub fn over(&self, backdrop: &Color) -> Self {
+        Self {
+            a: 1.0,
+            ..backdrop.lerp(&self.with_alpha(1.0), self.a)
+        }
+    }
+
+    /// WCAG relative luminance (ignores alpha)
+    pub fn relative_luminance(&self) -> f32 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+
+    /// WCAG contrast ratio between two colors, from 1.0 to 21.0
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        let (light, dark) = if a > b { (a, b) } else { (b, a) };
+        (light + 0.05) / (dark + 0.05)
+    }
+
+    /// Whether light text reads better on this color than dark text
+    pub fn is_dark(&self) -> bool {
+        self.contrast_ratio(&Color::WHITE) > self.contrast_ratio(&Color::BLACK)
+    }
+
+    /// Convert to HSL
+    pub fn to_hsl(&self) -> Hsl {
+        let (h, max, min) = hue_and_range(self);
+        let l = (max + min) / 2.0;
+        let delta = max - min;
+        let s = if delta == 0.0 {
+            0.0
+        } else {
+            delta / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l }
+    }
+
+    /// Convert from HSL
+    pub fn from_hsl(hsl: Hsl, alpha: f32) -> Self {
+        let c = (1.0 - (2.0 * hsl.l - 1.0).abs()) * hsl.s;
+        let (r, g, b) = from_hue(hsl.h, c);
+        let m = hsl.l - c / 2.0;
+        Self::new(r + m, g + m, b + m, alpha).clamped()
+    }
+
+    /// Convert to HSV
+    pub fn to_hsv(&self) -> Hsv {
+        let (h, max, min) = hue_and_range(self);
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h, s, v: max }
+    }
+
+    /// Convert from HSV
+    pub fn from_hsv(hsv: Hsv, alpha: f32) -> Self {
+        let c = hsv.v * hsv.s;
+        let (r, g, b) = from_hue(hsv.h, c);
+        let m = hsv.v - c;
+        Self::new(r + m, g + m, b + m, alpha).clamped()
+    }
+
+    /// Convert to OKLab
+    pub fn to_oklab(&self) -> Oklab {
+        let (r, g, b) = (to_linear(self.r), to_linear(self.g), to_linear(self.b));
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        Oklab {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    /// Convert from OKLab; out-of-gamut results are clamped to sRGB
+    pub fn from_oklab(lab: Oklab, alpha: f32) -> Self {
+        let l = (lab.l + 0.396_337_78 * lab.a + 0.215_803_76 * lab.b).powi(3);
+        let m = (lab.l - 0.105_561_346 * lab.a - 0.063_854_17 * lab.b).powi(3);
+        let s = (lab.l - 0.089_484_18 * lab.a - 1.291_485_5 * lab.b).powi(3);
+
+        Self::new(
+            from_linear(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            from_linear(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+            from_linear(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+            alpha,
+        )
+        .clamped()
+    }
+
+    /// Convert to OKLCH
+    pub fn to_oklch(&self) -> Oklch {
+        let lab = self.to_oklab();
+        Oklch {
+            l: lab.l,
+            c: (lab.a * lab.a + lab.b * lab.b).sqrt(),
+            h: lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0),
+        }
+    }
+
+    /// Convert from OKLCH; out-of-gamut results are clamped to sRGB
+    pub fn from_oklch(lch: Oklch, alpha: f32) -> Self {
+        let h = lch.h.to_radians();
+        Self::from_oklab(
+            Oklab {
+                l: lch.l,
+                a: lch.c * h.cos(),
+                b: lch.c * h.sin(),
+            },
+            alpha,
+        )
+    }
+
+    fn clamped(self) -> Self {
+        Self::new(
+            self.r.clamp(0.0, 1.0),
+            self.g.clamp(0.0, 1.0),
+            self.b.clamp(0.0, 1.0),
+            self.a.clamp(0.0, 1.0),
+        )
+    }
+}
+
+/// sRGB transfer function, encoded -> linear
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// sRGB transfer function, linear -> encoded
+fn from_linear(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Hue in degrees plus the max and min channel, shared by HSL and HSV
+fn hue_and_range(color: &Color) -> (f32, f32, f32) {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == color.r {
+        60.0 * ((color.g - color.b) / delta).rem_euclid(6.0)
+    } else if max == color.g {
+        60.0 * ((color.b - color.r) / delta + 2.0)
+    } else {
+        60.0 * ((color.r - color.g) / delta + 4.0)
+    };
+
+    (h, max, min)
+}
+
+/// RGB for a hue and chroma, before adding the lightness offset
+fn from_hue(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
+
+/// Parse any CSS color value (hex, rgb(), hsl(), named colors, ...)
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::css::parse_color(s).map_err(|e| ColorError::Css(e.to_string()))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
 }
 
 impl Default for Color {
@@ -95,12 +383,58 @@ impl Default for Color {
     }
 }
 
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a CSS color string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Color, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    /// Older serialized themes stored `{ r, g, b, a }` floats
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Color, A::Error> {
+        let mut color = Color::default();
+        while let Some(key) = map.next_key::<String>()? {
+            let value: f32 = map.next_value()?;
+            match key.as_str() {
+                "r" => color.r = value,
+                "g" => color.g = value,
+                "b" => color.b = value,
+                "a" => color.a = value,
+                other => return Err(de::Error::unknown_field(other, &["r", "g", "b", "a"])),
+            }
+        }
+        Ok(color)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ColorError {
     #[error("Invalid hex color length: {0}")]
     InvalidLength(usize),
     #[error("Invalid hex digit: {0}")]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("Invalid hex digit in {0:?}")]
+    InvalidDigit(String),
+    #[error("Invalid CSS color: {0}")]
+    Css(String),
 }
 
 #[cfg(test)]
@@ -117,4 +451,55 @@ mod tests {
         let color = Color::from_hex("#1a1b26").unwrap();
         assert!((color.r - 0.102).abs() < 0.01);
     }
+
+    #[test]
+    fn rejects_non_hex_digits() {
+        for hex in ["#é12", "#12é", "é1234", "#+ff", "#12345g"] {
+            assert!(matches!(Color::from_hex(hex), Err(ColorError::InvalidDigit(_))), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(Color::from_hex("#1a1b26").unwrap().to_hex(), "#1a1b26");
+        assert_eq!(Color::from_hex("#1a1b2680").unwrap().to_hex(), "#1a1b2680");
+        assert_eq!(
+            Color::from_hex("#ff8000").unwrap().to_css(),
+            "rgb(255, 128, 0)"
+        );
+    }
+
+    #[test]
+    fn color_space_round_trips() {
+        let color = Color::from_hex("#7aa2f7").unwrap();
+        for back in [
+            Color::from_hsl(color.to_hsl(), 1.0),
+            Color::from_hsv(color.to_hsv(), 1.0),
+            Color::from_oklab(color.to_oklab(), 1.0),
+            Color::from_oklch(color.to_oklch(), 1.0),
+        ] {
+            assert_eq!(back.to_hex(), color.to_hex());
+        }
+
+        let white = Color::WHITE.to_oklab();
+        assert!((white.l - 1.0).abs() < 0.001 && white.a.abs() < 0.001);
+    }
+
+    #[test]
+    fn wcag_contrast() {
+        assert!((Color::BLACK.contrast_ratio(&Color::WHITE) - 21.0).abs() < 0.01);
+        assert!((Color::WHITE.contrast_ratio(&Color::WHITE) - 1.0).abs() < 0.01);
+        assert!(Color::from_hex("#1e1e2e").unwrap().is_dark());
+        assert!(!Color::from_hex("#eff1f5").unwrap().is_dark());
+    }
+
+    #[test]
+    fn serde_uses_hex_strings() {
+        let color = Color::from_hex("#89b4fa").unwrap();
+        assert_eq!(serde_json::to_string(&color).unwrap(), "\"#89b4fa\"");
+        let back: Color = serde_json::from_str("\"rgb(137, 180, 250)\"").unwrap();
+        assert_eq!(back, color);
+        let legacy: Color = serde_json::from_str(r#"{"r":1.0,"g":0.0,"b":0.0,"a":1.0}"#).unwrap();
+        assert_eq!(legacy.to_hex(), "#ff0000");
+    }
 }
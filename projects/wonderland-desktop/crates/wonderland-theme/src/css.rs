@@ -0,0 +1,372 @@
+//! GTK-style CSS color parsing
+//!
+//! Reads every `@define-color` declaration from a stylesheet (waybar.css,
+//! walker.css, ...). Values may be any CSS color (hex, `rgb()`/`rgba()`,
+//! `hsl()`/`hsla()`, `hwb()`, `oklab()`/`oklch()`, named colors), a reference
+//! to another defined color (`@name`), or one of GTK's color functions:
+//! `alpha()`, `shade()`, `mix()`, `lighter()` and `darker()`.
+
+use crate::color::{Color, Oklab, Oklch};
+use cssparser::{
+    ColorParser, Delimiter, ParseError, ParseErrorKind, Parser, ParserInput, SourceLocation, Token,
+};
+use std::collections::BTreeMap;
+
+/// Parse all `@define-color` declarations into a name -> color map.
+///
+/// Definitions that reference colors not defined in this file (typically
+/// GTK theme colors like `@theme_fg_color`) are skipped with a warning.
+pub fn parse_define_colors(css: &str) -> Result<BTreeMap<String, Color>, CssError> {
+    let defined = define_colors(css)?;
+    for (name, missing) in defined.undefined {
+        tracing::warn!(
+            "Skipping @{}: references undefined color @{}",
+            name,
+            missing
+        );
+    }
+    Ok(defined.colors)
+}
+
+/// Resolved `@define-color`s, and the ones that couldn't be
+pub(crate) struct DefinedColors {
+    pub colors: BTreeMap<String, Color>,
+    /// (name, undefined color it references)
+    pub undefined: Vec<(String, String)>,
+}
+
+/// Like [`parse_define_colors`], keeping track of skipped definitions
+pub(crate) fn define_colors(css: &str) -> Result<DefinedColors, CssError> {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut definitions = BTreeMap::new();
+
+    while let Ok(token) = parser.next() {
+        let is_define =
+            matches!(token, Token::AtKeyword(kw) if kw.eq_ignore_ascii_case("define-color"));
+        if !is_define {
+            continue;
+        }
+
+        let (name, expr) = parser
+            .parse_until_after(Delimiter::Semicolon, |p| {
+                let name = p.expect_ident()?.to_string();
+                let expr = parse_expr(p)?;
+                Ok((name, expr))
+            })
+            .map_err(CssError::from_parse)?;
+
+        // Like GTK, a later definition replaces an earlier one
+        definitions.insert(name, expr);
+    }
+
+    let mut resolved = BTreeMap::new();
+    let mut undefined = Vec::new();
+    for name in definitions.keys() {
+        let mut stack = Vec::new();
+        match resolve(name, &definitions, &mut resolved, &mut stack) {
+            Ok(_) => {}
+            Err(CssError::Undefined(missing)) => undefined.push((name.clone(), missing)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(DefinedColors {
+        colors: resolved,
+        undefined,
+    })
+}
+
+/// Parse a single color value (no `@references`)
+pub fn parse_color(value: &str) -> Result<Color, CssError> {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let expr = parser
+        .parse_entirely(parse_expr)
+        .map_err(CssError::from_parse)?;
+    eval(
+        &expr,
+        &BTreeMap::new(),
+        &mut BTreeMap::new(),
+        &mut Vec::new(),
+    )
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CssError {
+    #[error("line {line}, column {column}: {message}")]
+    Syntax {
+        line: u32,
+        column: u32,
+        message: String,
+    },
+
+    #[error("undefined color @{0}")]
+    Undefined(String),
+
+    #[error("color @{0} is defined in terms of itself")]
+    Cycle(String),
+}
+
+impl CssError {
+    fn from_parse(error: ParseError<'_, String>) -> Self {
+        let message = match error.kind {
+            ParseErrorKind::Custom(message) => message,
+            ParseErrorKind::Basic(kind) => format!("{:?}", kind),
+        };
+        CssError::Syntax {
+            line: error.location.line + 1,
+            column: error.location.column,
+            message,
+        }
+    }
+}
+
+/// Unresolved color value
+#[derive(Debug, Clone)]
+enum ColorExpr {
+    Literal(Color),
+    Ref(String),
+    Alpha(Box<ColorExpr>, f32),
+    Shade(Box<ColorExpr>, f32),
+    Mix(Box<ColorExpr>, Box<ColorExpr>, f32),
+}
+
+/// Standard CSS color syntax, using cssparser's default component parsing
+struct CssColorParser;
+
+impl<'i> ColorParser<'i> for CssColorParser {
+    type Output = cssparser::Color;
+    type Error = String;
+}
+
+fn parse_expr<'i>(p: &mut Parser<'i, '_>) -> Result<ColorExpr, ParseError<'i, String>> {
+    let location = p.current_source_location();
+    let start = p.state();
+
+    match p.next()?.clone() {
+        Token::AtKeyword(name) => return Ok(ColorExpr::Ref(name.to_string())),
+        Token::Function(name) if is_gtk_function(&name) => {
+            return p.parse_nested_block(|args| parse_gtk_function(&name, args));
+        }
+        _ => p.reset(&start),
+    }
+
+    let color = cssparser::parse_color_with(&CssColorParser, p)?;
+    convert_color(color, location).map(ColorExpr::Literal)
+}
+
+fn is_gtk_function(name: &str) -> bool {
+    ["alpha", "shade", "mix", "lighter", "darker"]
+        .iter()
+        .any(|f| name.eq_ignore_ascii_case(f))
+}
+
+fn parse_gtk_function<'i>(
+    name: &str,
+    args: &mut Parser<'i, '_>,
+) -> Result<ColorExpr, ParseError<'i, String>> {
+    let name = name.to_ascii_lowercase();
+    let first = Box::new(parse_expr(args)?);
+
+    Ok(match name.as_str() {
+        "alpha" => {
+            args.expect_comma()?;
+            ColorExpr::Alpha(first, args.expect_number()?)
+        }
+        "shade" => {
+            args.expect_comma()?;
+            ColorExpr::Shade(first, args.expect_number()?)
+        }
+        "mix" => {
+            args.expect_comma()?;
+            let second = Box::new(parse_expr(args)?);
+            args.expect_comma()?;
+            ColorExpr::Mix(first, second, args.expect_number()?)
+        }
+        // Same factors GTK uses
+        "lighter" => ColorExpr::Shade(first, 1.3),
+        "darker" => ColorExpr::Shade(first, 0.7),
+        _ => unreachable!("checked by is_gtk_function"),
+    })
+}
+
+fn convert_color<'i>(
+    color: cssparser::Color,
+    location: SourceLocation,
+) -> Result<Color, ParseError<'i, String>> {
+    match color {
+        cssparser::Color::Rgba(rgba) => Ok(Color::new(
+            rgba.red.unwrap_or(0) as f32 / 255.0,
+            rgba.green.unwrap_or(0) as f32 / 255.0,
+            rgba.blue.unwrap_or(0) as f32 / 255.0,
+            rgba.alpha.unwrap_or(1.0),
+        )),
+        cssparser::Color::Hsl(hsl) => {
+            let (r, g, b) = cssparser::hsl_to_rgb(
+                hsl.hue.unwrap_or(0.0) / 360.0,
+                hsl.saturation.unwrap_or(0.0),
+                hsl.lightness.unwrap_or(0.0),
+            );
+            Ok(Color::new(r, g, b, hsl.alpha.unwrap_or(1.0)))
+        }
+        cssparser::Color::Hwb(hwb) => {
+            let (r, g, b) = cssparser::hwb_to_rgb(
+                hwb.hue.unwrap_or(0.0) / 360.0,
+                hwb.whiteness.unwrap_or(0.0),
+                hwb.blackness.unwrap_or(0.0),
+            );
+            Ok(Color::new(r, g, b, hwb.alpha.unwrap_or(1.0)))
+        }
+        cssparser::Color::Oklab(lab) => Ok(Color::from_oklab(
+            Oklab {
+                l: lab.lightness.unwrap_or(0.0),
+                a: lab.a.unwrap_or(0.0),
+                b: lab.b.unwrap_or(0.0),
+            },
+            lab.alpha.unwrap_or(1.0),
+        )),
+        cssparser::Color::Oklch(lch) => Ok(Color::from_oklch(
+            Oklch {
+                l: lch.lightness.unwrap_or(0.0),
+                c: lch.chroma.unwrap_or(0.0),
+                h: lch.hue.unwrap_or(0.0),
+            },
+            lch.alpha.unwrap_or(1.0),
+        )),
+        cssparser::Color::CurrentColor => {
+            Err(location
+                .new_custom_error("currentColor cannot be used in @define-color".to_string()))
+        }
+        _ => Err(location.new_custom_error("unsupported color space".to_string())),
+    }
+}
+
+fn resolve(
+    name: &str,
+    definitions: &BTreeMap<String, ColorExpr>,
+    resolved: &mut BTreeMap<String, Color>,
+    stack: &mut Vec<String>,
+) -> Result<Color, CssError> {
+    if let Some(color) = resolved.get(name) {
+        return Ok(*color);
+    }
+    if stack.iter().any(|n| n == name) {
+        return Err(CssError::Cycle(name.to_string()));
+    }
+    let expr = definitions
+        .get(name)
+        .ok_or_else(|| CssError::Undefined(name.to_string()))?;
+
+    stack.push(name.to_string());
+    let color = eval(expr, definitions, resolved, stack)?;
+    stack.pop();
+
+    resolved.insert(name.to_string(), color);
+    Ok(color)
+}
+
+fn eval(
+    expr: &ColorExpr,
+    definitions: &BTreeMap<String, ColorExpr>,
+    resolved: &mut BTreeMap<String, Color>,
+    stack: &mut Vec<String>,
+) -> Result<Color, CssError> {
+    Ok(match expr {
+        ColorExpr::Literal(color) => *color,
+        ColorExpr::Ref(name) => resolve(name, definitions, resolved, stack)?,
+        ColorExpr::Alpha(inner, factor) => {
+            let color = eval(inner, definitions, resolved, stack)?;
+            color.with_alpha((color.a * factor).clamp(0.0, 1.0))
+        }
+        ColorExpr::Shade(inner, factor) => {
+            shade(eval(inner, definitions, resolved, stack)?, *factor)
+        }
+        ColorExpr::Mix(a, b, factor) => {
+            let a = eval(a, definitions, resolved, stack)?;
+            let b = eval(b, definitions, resolved, stack)?;
+            let f = factor.clamp(0.0, 1.0);
+            Color::new(
+                a.r + (b.r - a.r) * f,
+                a.g + (b.g - a.g) * f,
+                a.b + (b.b - a.b) * f,
+                a.a + (b.a - a.a) * f,
+            )
+        }
+    })
+}
+
+/// GTK's shade(): scale lightness and saturation in HSL space
+fn shade(color: Color, factor: f32) -> Color {
+    let mut hsl = color.to_hsl();
+    hsl.l = (hsl.l * factor).clamp(0.0, 1.0);
+    hsl.s = (hsl.s * factor).clamp(0.0, 1.0);
+    Color::from_hsl(hsl, color.a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: Color, b: Color) -> bool {
+        (a.r - b.r).abs() < 0.01
+            && (a.g - b.g).abs() < 0.01
+            && (a.b - b.b).abs() < 0.01
+            && (a.a - b.a).abs() < 0.01
+    }
+
+    #[test]
+    fn parses_functional_and_named_colors() {
+        let colors = parse_define_colors(
+            r#"
+            /* Catppuccin */
+            @define-color background rgba(30, 30, 46, 0.9);
+            @define-color accent hsl(0, 100%, 50%);
+            @define-color white white;
+            @define-color fg #cdd6f4;
+
+            * { color: @fg; }
+            "#,
+        )
+        .unwrap();
+
+        assert!(approx(
+            colors["background"],
+            Color::new(30.0 / 255.0, 30.0 / 255.0, 46.0 / 255.0, 0.9)
+        ));
+        assert!(approx(colors["accent"], Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert!(approx(colors["white"], Color::new(1.0, 1.0, 1.0, 1.0)));
+        assert!(approx(colors["fg"], Color::from_hex("#cdd6f4").unwrap()));
+    }
+
+    #[test]
+    fn resolves_references_and_gtk_functions() {
+        let colors = parse_define_colors(
+            r#"
+            @define-color foreground @text;
+            @define-color text #ffffff;
+            @define-color faded alpha(@text, 0.5);
+            @define-color half mix(#000000, @text, 0.5);
+            @define-color dim shade(@text, 0.5);
+            @define-color broken @theme_fg_color;
+            "#,
+        )
+        .unwrap();
+
+        assert!(approx(colors["foreground"], Color::new(1.0, 1.0, 1.0, 1.0)));
+        assert!(approx(colors["faded"], Color::new(1.0, 1.0, 1.0, 0.5)));
+        assert!(approx(colors["half"], Color::new(0.5, 0.5, 0.5, 1.0)));
+        assert!(approx(colors["dim"], Color::new(0.5, 0.5, 0.5, 1.0)));
+        assert!(!colors.contains_key("broken"));
+    }
+
+    #[test]
+    fn reports_position_and_cycles() {
+        let err =
+            parse_define_colors("@define-color a #fff;\n@define-color b rgb(1, 2);").unwrap_err();
+        assert!(matches!(err, CssError::Syntax { line: 2, .. }));
+
+        let err = parse_define_colors("@define-color a @b;\n@define-color b @a;").unwrap_err();
+        assert!(matches!(err, CssError::Cycle(_)));
+    }
+}
@@ -0,0 +1,336 @@
+//! Theme export through templates
+//!
+//! Renders every app config a theme directory ships (waybar.css, mako.ini,
+//! kitty.conf, ...) from a single [`Theme`]. Templates substitute
+//! `{{ expression }}` blocks, where an expression is a variable followed by
+//! optional filters:
+//!
+//! ```text
+//! background = "{{ background }}"
+//! col.active_border = rgba({{ primary | alpha(0.9) | hexa | bare }})
+//! ```
+//!
+//! Variables are the color roles (`primary`, `text_muted`, ...), any
+//! `@define-color` from waybar.css as `named.<name>`, the terminal palette
+//! as `terminal.<key>` (`terminal.color9`, `terminal.red`, `terminal.cursor`,
+//! ...), design tokens as `tokens.<path>` (`tokens.radius.large`,
+//! `tokens.font.family`, ...), and the metadata strings `name`,
+//! `display_name`, `author` and `variant`.
+//!
+//! Color filters: `lighten(f)`, `darken(f)`, `alpha(a)`.
+//! Formatting filters: `hex` (the default, opaque), `hexa` (with alpha),
+//! `bare` (drop the leading `#`), `rgb`, `rgba` and `rgba(a)`.
+
+use crate::color::Color;
+use crate::loader::{Role, Theme, ThemeError};
+use crate::theme_file::{ThemeFile, Variant};
+use std::path::{Path, PathBuf};
+
+/// Built-in templates, keyed by the file name they render to
+pub const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("waybar.css", include_str!("../templates/waybar.css")),
+    ("walker.css", include_str!("../templates/walker.css")),
+    ("mako.ini", include_str!("../templates/mako.ini")),
+    ("hyprland.conf", include_str!("../templates/hyprland.conf")),
+    ("hyprlock.conf", include_str!("../templates/hyprlock.conf")),
+    ("alacritty.toml", include_str!("../templates/alacritty.toml")),
+    ("kitty.conf", include_str!("../templates/kitty.conf")),
+    ("ghostty.conf", include_str!("../templates/ghostty.conf")),
+    ("btop.theme", include_str!("../templates/btop.theme")),
+];
+
+/// Renders theme directories from templates
+pub struct Exporter {
+    templates: Vec<(String, String)>,
+}
+
+impl Exporter {
+    /// Built-in templates, overridden by any file with the same name in
+    /// `~/.config/wonderland/templates`
+    pub fn new() -> Result<Self, ThemeError> {
+        Self::builtin().with_template_dir(&wonderland_config::config_dir().join("templates"))
+    }
+
+    /// Only the built-in templates
+    pub fn builtin() -> Self {
+        Self {
+            templates: BUILTIN_TEMPLATES
+                .iter()
+                .map(|(name, body)| (name.to_string(), body.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Load templates from `dir`, replacing built-ins with the same file name
+    /// and adding any new ones. A missing directory is not an error.
+    pub fn with_template_dir(mut self, dir: &Path) -> Result<Self, ThemeError> {
+        if !dir.is_dir() {
+            return Ok(self);
+        }
+
+        let entries = std::fs::read_dir(dir).map_err(|e| ThemeError::Io(dir.to_path_buf(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| ThemeError::Io(dir.to_path_buf(), e))?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if !path.is_file() {
+                continue;
+            }
+
+            let body = std::fs::read_to_string(&path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+            match self.templates.iter_mut().find(|(n, _)| *n == name) {
+                Some(slot) => slot.1 = body,
+                None => self.templates.push((name, body)),
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Render every template, returning (file name, contents)
+    pub fn render(&self, theme: &Theme) -> Result<Vec<(String, String)>, ThemeError> {
+        self.templates
+            .iter()
+            .map(|(name, body)| {
+                render_template(body, theme)
+                    .map(|out| (name.clone(), out))
+                    .map_err(|e| ThemeError::Template(name.clone(), e))
+            })
+            .collect()
+    }
+
+    /// Write a complete theme directory: rendered templates, a theme.toml
+    /// with every role, and a copy of the theme's backgrounds.
+    pub fn export(&self, theme: &Theme, dest: &Path) -> Result<Vec<PathBuf>, ThemeError> {
+        let rendered = self.render(theme)?;
+
+        std::fs::create_dir_all(dest).map_err(|e| ThemeError::Io(dest.to_path_buf(), e))?;
+
+        let mut written = Vec::new();
+        for (name, contents) in rendered {
+            let path = dest.join(name);
+            std::fs::write(&path, contents).map_err(|e| ThemeError::Io(path.clone(), e))?;
+            written.push(path);
+        }
+
+        let theme_toml = dest.join("theme.toml");
+        let toml = toml::to_string_pretty(&ThemeFile::from_theme(theme))
+            .map_err(|e| ThemeError::Serialize(theme_toml.clone(), e))?;
+        std::fs::write(&theme_toml, toml).map_err(|e| ThemeError::Io(theme_toml.clone(), e))?;
+        written.push(theme_toml);
+
+        let backgrounds = theme.path.join("backgrounds");
+        if backgrounds.is_dir() && theme.path != dest {
+            written.extend(copy_dir(&backgrounds, &dest.join("backgrounds"))?);
+        }
+
+        Ok(written)
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<Vec<PathBuf>, ThemeError> {
+    std::fs::create_dir_all(to).map_err(|e| ThemeError::Io(to.to_path_buf(), e))?;
+
+    let mut copied = Vec::new();
+    let entries = std::fs::read_dir(from).map_err(|e| ThemeError::Io(from.to_path_buf(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| ThemeError::Io(from.to_path_buf(), e))?.path();
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let target = to.join(name);
+        if path.is_dir() {
+            copied.extend(copy_dir(&path, &target)?);
+        } else {
+            std::fs::copy(&path, &target).map_err(|e| ThemeError::Io(target.clone(), e))?;
+            copied.push(target);
+        }
+    }
+
+    Ok(copied)
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct TemplateError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Render a single template against a theme
+pub fn render_template(template: &str, theme: &Theme) -> Result<String, TemplateError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut line = 1;
+
+    while let Some(start) = rest.find("{{") {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+        line += before.matches('\n').count();
+
+        let end = after.find("}}").ok_or_else(|| TemplateError {
+            line,
+            message: "unclosed `{{`".to_string(),
+        })?;
+        let expr = &after[2..end];
+
+        let value = eval_expr(expr, theme).map_err(|message| TemplateError { line, message })?;
+        out.push_str(&value);
+
+        line += expr.matches('\n').count();
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+enum Value {
+    Color(Color),
+    Text(String),
+}
+
+impl Value {
+    fn into_string(self) -> String {
+        match self {
+            Value::Color(color) => color.with_alpha(1.0).to_hex(),
+            Value::Text(text) => text,
+        }
+    }
+}
+
+fn eval_expr(expr: &str, theme: &Theme) -> Result<String, String> {
+    let mut parts = expr.split('|').map(str::trim);
+    let variable = parts.next().unwrap_or_default();
+    let mut value = lookup(variable, theme)?;
+
+    for filter in parts {
+        value = apply_filter(filter, value)?;
+    }
+
+    Ok(value.into_string())
+}
+
+fn lookup(variable: &str, theme: &Theme) -> Result<Value, String> {
+    if let Some(role) = Role::from_name(variable) {
+        return Ok(Value::Color(theme.color(role)));
+    }
+    if let Some(name) = variable.strip_prefix("named.") {
+        return theme
+            .named(name)
+            .map(Value::Color)
+            .ok_or_else(|| format!("theme has no color named `{}`", name));
+    }
+    if let Some(path) = variable.strip_prefix("tokens.") {
+        return theme
+            .tokens
+            .get(path)
+            .map(Value::Text)
+            .ok_or_else(|| format!("unknown token `{}`", path));
+    }
+    if let Some(key) = variable.strip_prefix("terminal.") {
+        return theme
+            .terminal
+            .get(key)
+            .map(Value::Color)
+            .ok_or_else(|| format!("unknown terminal color `{}`", key));
+    }
+
+    Ok(Value::Text(match variable {
+        "name" => theme.name.clone(),
+        "display_name" => theme.meta.display_name.clone(),
+        "author" => theme.meta.author.clone().unwrap_or_default(),
+        "variant" => match theme.meta.variant {
+            Variant::Dark => "dark".to_string(),
+            Variant::Light => "light".to_string(),
+        },
+        _ => return Err(format!("unknown variable `{}`", variable)),
+    }))
+}
+
+fn apply_filter(filter: &str, value: Value) -> Result<Value, String> {
+    let (name, arg) = match filter.split_once('(') {
+        Some((name, arg)) => {
+            let arg = arg
+                .strip_suffix(')')
+                .ok_or_else(|| format!("missing `)` in filter `{}`", filter))?;
+            let arg: f32 = arg
+                .trim()
+                .parse()
+                .map_err(|_| format!("filter `{}` expects a number", name.trim()))?;
+            (name.trim(), Some(arg))
+        }
+        None => (filter, None),
+    };
+
+    if name == "bare" {
+        return Ok(Value::Text(value.into_string().trim_start_matches('#').to_string()));
+    }
+
+    let Value::Color(color) = value else {
+        return Err(format!("filter `{}` needs a color", name));
+    };
+    let require = |arg: Option<f32>| arg.ok_or_else(|| format!("filter `{}` needs an argument", name));
+
+    Ok(match name {
+        "lighten" => Value::Color(color.lighten(require(arg)?)),
+        "darken" => Value::Color(color.darken(require(arg)?)),
+        "alpha" => Value::Color(color.with_alpha(require(arg)?)),
+        "hex" => Value::Text(color.with_alpha(1.0).to_hex()),
+        "hexa" => {
+            let [r, g, b, a] = color.to_rgba8();
+            Value::Text(format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a))
+        }
+        "rgb" => Value::Text(color.with_alpha(1.0).to_css()),
+        "rgba" => {
+            let [r, g, b, _] = color.to_rgba8();
+            let a = arg.unwrap_or(color.a);
+            Value::Text(format!("rgba({}, {}, {}, {})", r, g, b, (a * 1000.0).round() / 1000.0))
+        }
+        _ => return Err(format!("unknown filter `{}`", name)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> Theme {
+        let mut theme = Theme::derive_extended_colors(
+            Color::from_hex("#cdd6f4").unwrap(),
+            Color::from_hex("#1e1e2e").unwrap(),
+        );
+        theme.primary = Color::from_hex("#89b4fa").unwrap();
+        theme.meta.display_name = "Catppuccin".to_string();
+        theme
+    }
+
+    #[test]
+    fn renders_variables_and_filters() {
+        let out = render_template(
+            "# {{ display_name }}\nbg={{background}} fg={{ foreground | bare }}\n\
+             border=rgba({{ primary | alpha(0.5) | hexa | bare }}) sel={{ primary | rgba(0.25) }}",
+            &theme(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            out,
+            "# Catppuccin\nbg=#1e1e2e fg=cdd6f4\nborder=rgba(89b4fa80) sel=rgba(137, 180, 250, 0.25)"
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        let err = render_template("a\nb\n{{ primary | blur(2) }}", &theme()).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("blur"));
+    }
+
+    #[test]
+    fn builtin_templates_render() {
+        let rendered = Exporter::builtin().render(&theme()).unwrap();
+        assert_eq!(rendered.len(), BUILTIN_TEMPLATES.len());
+    }
+}
@@ -0,0 +1,381 @@
+//! Theme generation from wallpapers
+//!
+//! Decodes a wallpaper, clusters its pixels with k-means in OKLab, and picks
+//! background, foreground and accent colors from the clusters, adjusting
+//! their lightness until they meet the requested contrast level.
+
+use crate::audit::WcagLevel;
+use crate::color::{Color, Oklab, Oklch};
+use crate::export::Exporter;
+use crate::loader::{validate_name, Role, Theme, ThemeError, ThemeLoader};
+use crate::terminal::TerminalPalette;
+use crate::theme_file::Variant;
+use image::DynamicImage;
+use std::path::Path;
+
+/// Longest side the wallpaper is downscaled to before clustering
+const SAMPLE_SIZE: u32 = 96;
+
+/// k-means stops after this many rounds even if it hasn't settled
+const MAX_ITERATIONS: usize = 24;
+
+/// Minimum OKLCH chroma for a cluster to count as an accent
+const ACCENT_CHROMA: f32 = 0.04;
+
+/// OKLCH hues the status roles are matched against
+const STATUS_HUES: [(Role, f32); 3] = [
+    (Role::Error, 25.0),
+    (Role::Warning, 85.0),
+    (Role::Success, 145.0),
+];
+
+/// A dominant color of an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+    pub color: Color,
+    /// Share of the sampled pixels, 0.0-1.0
+    pub weight: f32,
+}
+
+/// Builds themes from wallpapers
+#[derive(Debug, Clone)]
+pub struct Generator {
+    clusters: usize,
+    variant: Option<Variant>,
+    level: WcagLevel,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator {
+    /// Eight clusters, variant picked from the image, WCAG AA contrast
+    pub fn new() -> Self {
+        Self {
+            clusters: 8,
+            variant: None,
+            level: WcagLevel::Aa,
+        }
+    }
+
+    /// Number of k-means clusters
+    pub fn with_clusters(mut self, clusters: usize) -> Self {
+        self.clusters = clusters.max(1);
+        self
+    }
+
+    /// Force a dark or light theme instead of following the image
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+
+    /// Contrast level every text pairing must reach
+    pub fn with_contrast(mut self, level: WcagLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Dominant colors of an image, most common first
+    pub fn palette(&self, image: &DynamicImage) -> Vec<Swatch> {
+        let pixels: Vec<Oklab> = image
+            .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+            .to_rgba8()
+            .pixels()
+            .filter(|p| p[3] >= 128)
+            .map(|p| Color::new(p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0, 1.0).to_oklab())
+            .collect();
+
+        kmeans(&pixels, self.clusters)
+    }
+
+    /// Build a theme from an image's palette
+    pub fn theme(&self, image: &DynamicImage, name: &str) -> Theme {
+        self.theme_from_palette(&self.palette(image), name)
+    }
+
+    /// Build a theme from a palette as returned by [`Generator::palette`]
+    pub fn theme_from_palette(&self, swatches: &[Swatch], name: &str) -> Theme {
+        let dominant = swatches
+            .first()
+            .map(|s| s.color.to_oklch())
+            .unwrap_or(Oklch { l: 0.2, c: 0.0, h: 0.0 });
+        let mean_l: f32 = swatches.iter().map(|s| s.color.to_oklab().l * s.weight).sum();
+        let variant = self.variant.unwrap_or(if mean_l < 0.6 {
+            Variant::Dark
+        } else {
+            Variant::Light
+        });
+        let dark = variant == Variant::Dark;
+
+        // Tint the background and text with the dominant hue
+        let background = Color::from_oklch(
+            Oklch {
+                l: if dark {
+                    dominant.l.clamp(0.16, 0.26)
+                } else {
+                    dominant.l.clamp(0.93, 0.97)
+                },
+                c: dominant.c.min(0.035),
+                h: dominant.h,
+            },
+            1.0,
+        );
+        let foreground = fit(
+            Oklch {
+                l: if dark { 0.9 } else { 0.3 },
+                c: dominant.c.min(0.02),
+                h: dominant.h,
+            },
+            &background,
+            WcagLevel::Aaa.min_ratio(),
+        );
+
+        // Colorful clusters, the most prominent first
+        let mut scored: Vec<(Oklch, f32)> = swatches
+            .iter()
+            .map(|s| (s.color.to_oklch(), s.weight))
+            .filter(|(lch, _)| lch.c >= ACCENT_CHROMA)
+            .map(|(lch, weight)| (lch, lch.c * weight.sqrt()))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let accents: Vec<Oklch> = scored.into_iter().map(|(lch, _)| lch).collect();
+
+        let primary = accents.first().copied().unwrap_or(Oklch {
+            l: 0.7,
+            c: 0.12,
+            h: (dominant.h + 180.0).rem_euclid(360.0),
+        });
+        let secondary = accents
+            .iter()
+            .copied()
+            .find(|lch| hue_distance(lch.h, primary.h) >= 40.0)
+            .unwrap_or(Oklch {
+                h: (primary.h + 60.0).rem_euclid(360.0),
+                ..primary
+            });
+
+        let ratio = self.level.min_ratio();
+        let mut theme = Theme::derive_extended_colors(foreground, background);
+        theme.primary = fit(primary, &background, ratio);
+        theme.secondary = fit(secondary, &background, ratio);
+        theme.border_active = theme.primary;
+
+        // Status colors keep their conventional hue, borrowing a nearby
+        // wallpaper color when there is one
+        for (role, hue) in STATUS_HUES {
+            let lch = accents
+                .iter()
+                .copied()
+                .filter(|lch| hue_distance(lch.h, hue) <= 25.0)
+                .min_by(|a, b| hue_distance(a.h, hue).total_cmp(&hue_distance(b.h, hue)))
+                .unwrap_or(Oklch {
+                    l: primary.l,
+                    c: primary.c.max(0.12),
+                    h: hue,
+                });
+            *theme.color_mut(role) = fit(lch, &background, ratio);
+        }
+
+        theme.correct_contrast(self.level, &[Role::Foreground, Role::Background]);
+        theme.terminal = TerminalPalette::derive(&theme);
+
+        theme.name = name.to_string();
+        theme.meta.display_name = name.to_string();
+        theme.meta.variant = variant;
+        theme
+    }
+
+    /// Generate a theme from a wallpaper file and write it under the
+    /// loader's themes dir, with the wallpaper in its backgrounds
+    pub fn write(
+        &self,
+        loader: &ThemeLoader,
+        wallpaper: &Path,
+        name: &str,
+        overwrite: bool,
+    ) -> Result<Theme, ThemeError> {
+        validate_name(name)?;
+        let dest = loader.theme_dir(name);
+        if dest.exists() && !overwrite {
+            return Err(ThemeError::AlreadyExists(name.to_string()));
+        }
+
+        let image = image::open(wallpaper).map_err(|e| ThemeError::Image(wallpaper.to_path_buf(), e))?;
+        let mut theme = self.theme(&image, name);
+        theme.path = dest.clone();
+        Exporter::new()?.export(&theme, &dest)?;
+
+        let backgrounds = loader.backgrounds_dir(name);
+        std::fs::create_dir_all(&backgrounds).map_err(|e| ThemeError::Io(backgrounds.clone(), e))?;
+        if let Some(file_name) = wallpaper.file_name() {
+            let target = backgrounds.join(file_name);
+            std::fs::copy(wallpaper, &target).map_err(|e| ThemeError::Io(target, e))?;
+        }
+
+        Ok(theme)
+    }
+}
+
+/// Move `lch` in lightness, away from `background`, until it reaches `ratio`
+fn fit(mut lch: Oklch, background: &Color, ratio: f32) -> Color {
+    let step = if background.is_dark() { 0.01 } else { -0.01 };
+    loop {
+        let color = Color::from_oklch(lch, 1.0);
+        let next = lch.l + step;
+        if color.contrast_ratio(background) >= ratio || !(0.0..=1.0).contains(&next) {
+            return color;
+        }
+        lch.l = next;
+    }
+}
+
+/// Angle between two hues in degrees, 0-180
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let d = (a - b).rem_euclid(360.0);
+    d.min(360.0 - d)
+}
+
+/// Cluster OKLab colors, returning centroids by descending weight
+fn kmeans(pixels: &[Oklab], k: usize) -> Vec<Swatch> {
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+
+    // Deterministic start: evenly spaced picks along the lightness axis
+    let mut sorted = pixels.to_vec();
+    sorted.sort_by(|a, b| a.l.total_cmp(&b.l));
+    let k = k.min(sorted.len());
+    let mut centroids: Vec<Oklab> = (0..k)
+        .map(|i| sorted[(2 * i + 1) * sorted.len() / (2 * k)])
+        .collect();
+
+    let mut assignment = vec![0; pixels.len()];
+    for _ in 0..MAX_ITERATIONS {
+        for (slot, pixel) in assignment.iter_mut().zip(pixels) {
+            *slot = nearest(&centroids, pixel);
+        }
+
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0usize); centroids.len()];
+        for (&cluster, pixel) in assignment.iter().zip(pixels) {
+            let sum = &mut sums[cluster];
+            sum.0 += pixel.l;
+            sum.1 += pixel.a;
+            sum.2 += pixel.b;
+            sum.3 += 1;
+        }
+
+        let mut moved = 0.0f32;
+        for (centroid, (l, a, b, n)) in centroids.iter_mut().zip(&sums) {
+            if *n == 0 {
+                continue;
+            }
+            let n = *n as f32;
+            let next = Oklab {
+                l: l / n,
+                a: a / n,
+                b: b / n,
+            };
+            moved = moved.max(distance(centroid, &next));
+            *centroid = next;
+        }
+
+        if moved < 1e-4 {
+            break;
+        }
+    }
+
+    let mut counts = vec![0usize; centroids.len()];
+    for &cluster in &assignment {
+        counts[cluster] += 1;
+    }
+
+    let mut swatches: Vec<Swatch> = centroids
+        .into_iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(lab, count)| Swatch {
+            color: Color::from_oklab(lab, 1.0),
+            weight: count as f32 / pixels.len() as f32,
+        })
+        .collect();
+    swatches.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    swatches
+}
+
+fn nearest(centroids: &[Oklab], pixel: &Oklab) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a, pixel).total_cmp(&distance(b, pixel)))
+        .map_or(0, |(i, _)| i)
+}
+
+fn distance(a: &Oklab, b: &Oklab) -> f32 {
+    (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    /// Mostly night-sky blue with an orange sunset band
+    fn wallpaper() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |_, y| {
+            if y < 48 {
+                Rgba([24, 32, 64, 255])
+            } else {
+                Rgba([240, 140, 40, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn finds_dominant_colors() {
+        let swatches = Generator::new().palette(&wallpaper());
+        assert_eq!(swatches.len(), 2);
+        assert_eq!(swatches[0].color.to_hex(), "#182040");
+        assert!((swatches[0].weight - 0.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn generated_theme_meets_contrast() {
+        let theme = Generator::new().theme(&wallpaper(), "sunset");
+        assert_eq!(theme.meta.variant, Variant::Dark);
+        // The orange band becomes the accent
+        let hue = theme.primary.to_oklch().h;
+        assert!(hue_distance(hue, 55.0) < 20.0, "primary hue {}", hue);
+
+        let report = theme.audit();
+        assert!(
+            report.passes(WcagLevel::Aa),
+            "{:#?}",
+            report.failures(WcagLevel::Aa).collect::<Vec<_>>()
+        );
+
+        let light = Generator::new()
+            .with_variant(Variant::Light)
+            .theme(&wallpaper(), "sunset-light");
+        assert!(!light.background.is_dark());
+        assert!(light.audit().passes(WcagLevel::Aa));
+    }
+
+    #[test]
+    fn write_rejects_invalid_names() {
+        let root = tempfile::tempdir().unwrap();
+        let loader = ThemeLoader::with_paths(root.path().join("themes"), root.path().join("current/theme"));
+        let path = root.path().join("wallpaper.png");
+        wallpaper().save(&path).unwrap();
+
+        for name in ["", "../x", "東京"] {
+            let err = Generator::new().write(&loader, &path, name, false).unwrap_err();
+            assert!(matches!(err, ThemeError::InvalidName(_)), "{:?}: {}", name, err);
+        }
+        assert!(!root.path().join("themes").exists());
+        assert!(!root.path().join("x").exists());
+    }
+}
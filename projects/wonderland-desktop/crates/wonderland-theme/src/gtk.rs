@@ -0,0 +1,289 @@
+//! GTK and libadwaita colors
+//!
+//! Writes `gtk-3.0/gtk.css` and `gtk-4.0/gtk.css` in the user's config dir
+//! with `@define-color` overrides of libadwaita's named colors (and GTK3's
+//! `theme_*` ones), mapped from the theme's roles, so GTK apps follow the
+//! active theme.
+//!
+//! A gtk.css we didn't write is moved aside to `gtk.css.wonderland-backup`
+//! before the first write and `@import`ed by ours, and
+//! [`GtkExport::restore`] puts it back.
+
+use crate::export::render_template;
+use crate::hooks::{HookReport, HookStatus};
+use crate::loader::{Theme, ThemeError};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// gtk.css templates, keyed by the config subdirectory they go to
+const TEMPLATES: &[(&str, &str)] = &[
+    ("gtk-3.0", include_str!("../templates/gtk/gtk-3.0.css")),
+    ("gtk-4.0", include_str!("../templates/gtk/gtk-4.0.css")),
+];
+
+/// Marks files written by us, on their first line
+const MARKER: &str = "generated by wonderland-theme";
+
+const BACKUP_NAME: &str = "gtk.css.wonderland-backup";
+
+/// Writes and restores the GTK color overrides
+#[derive(Debug, Clone)]
+pub struct GtkExport {
+    config_dir: PathBuf,
+}
+
+impl Default for GtkExport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GtkExport {
+    /// Write into `$XDG_CONFIG_HOME`
+    pub fn new() -> Self {
+        Self::with_config_dir(wonderland_config::xdg_config_home())
+    }
+
+    /// Write into `config_dir` instead
+    pub fn with_config_dir(config_dir: PathBuf) -> Self {
+        Self { config_dir }
+    }
+
+    /// The gtk.css files this writes
+    pub fn paths(&self) -> Vec<PathBuf> {
+        TEMPLATES
+            .iter()
+            .map(|(dir, _)| self.config_dir.join(dir).join("gtk.css"))
+            .collect()
+    }
+
+    /// Render every gtk.css, returning (path, contents)
+    pub fn render(&self, theme: &Theme) -> Result<Vec<(PathBuf, String)>, ThemeError> {
+        TEMPLATES
+            .iter()
+            .map(|(dir, template)| {
+                let name = format!("{}/gtk.css", dir);
+                render_template(template, theme)
+                    .map(|css| (self.config_dir.join(&name), css))
+                    .map_err(|e| ThemeError::Template(name, e))
+            })
+            .collect()
+    }
+
+    /// Write the overrides for `theme`, backing up gtk.css files that
+    /// aren't ours and importing the backups. Either every file is written
+    /// or none is changed. Returns the files written.
+    pub fn write(&self, theme: &Theme) -> Result<Vec<PathBuf>, ThemeError> {
+        let mut pending = Vec::new();
+        for (path, css) in self.render(theme)? {
+            let backup = path.with_file_name(BACKUP_NAME);
+            let move_aside = exists(&path) && !is_ours(&path);
+            if move_aside && exists(&backup) {
+                return Err(ThemeError::Io(
+                    path,
+                    std::io::Error::new(
+                        std::io::ErrorKind::AlreadyExists,
+                        format!("not ours, and {} is already taken", backup.display()),
+                    ),
+                ));
+            }
+
+            let css = if move_aside || exists(&backup) {
+                import_backup(&css)
+            } else {
+                css
+            };
+            let previous = if move_aside {
+                None
+            } else {
+                std::fs::read_to_string(&path).ok()
+            };
+            pending.push(Pending {
+                path,
+                css,
+                move_aside,
+                previous,
+            });
+        }
+
+        for (i, file) in pending.iter().enumerate() {
+            if let Err(e) = file.apply() {
+                for file in pending[..=i].iter().rev() {
+                    file.undo();
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(pending.into_iter().map(|file| file.path).collect())
+    }
+
+    /// [`GtkExport::write`] as a step of a theme switch
+    pub(crate) fn write_step(&self, theme: &Theme) -> HookReport {
+        let started = Instant::now();
+        let status = match self.write(theme) {
+            Ok(_) => HookStatus::Ok,
+            Err(e) => {
+                tracing::warn!("writing GTK colors failed: {}", e);
+                HookStatus::Error(e.to_string())
+            }
+        };
+
+        HookReport {
+            name: "gtk".to_string(),
+            status,
+            duration: started.elapsed(),
+        }
+    }
+
+    /// Undo [`GtkExport::write`]: put backed up gtk.css files back and
+    /// remove the ones that had none. Files changed by someone else since
+    /// are left alone. Returns the files restored or removed.
+    pub fn restore(&self) -> Result<Vec<PathBuf>, ThemeError> {
+        let mut restored = Vec::new();
+        for path in self.paths() {
+            let backup = path.with_file_name(BACKUP_NAME);
+            if exists(&path) && !is_ours(&path) {
+                if exists(&backup) {
+                    tracing::warn!(
+                        "not restoring {}: it was changed since, the backup is at {}",
+                        path.display(),
+                        backup.display()
+                    );
+                }
+                continue;
+            }
+
+            if exists(&backup) {
+                std::fs::rename(&backup, &path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+                restored.push(path);
+            } else if exists(&path) {
+                std::fs::remove_file(&path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+                restored.push(path);
+            }
+        }
+
+        Ok(restored)
+    }
+}
+
+/// A gtk.css about to be written, with what it takes to undo that
+struct Pending {
+    path: PathBuf,
+    css: String,
+    /// The file there isn't ours and gets moved to the backup
+    move_aside: bool,
+    /// Our file being replaced
+    previous: Option<String>,
+}
+
+impl Pending {
+    fn apply(&self) -> Result<(), ThemeError> {
+        let dir = self.path.parent().expect("gtk.css has a parent dir");
+        std::fs::create_dir_all(dir).map_err(|e| ThemeError::Io(dir.to_path_buf(), e))?;
+
+        if self.move_aside {
+            let backup = self.path.with_file_name(BACKUP_NAME);
+            std::fs::rename(&self.path, &backup).map_err(|e| ThemeError::Io(backup.clone(), e))?;
+            tracing::info!("moved {} to {}", self.path.display(), backup.display());
+        }
+
+        let tmp = self.path.with_extension(format!("css.{}.tmp", std::process::id()));
+        std::fs::write(&tmp, &self.css).map_err(|e| ThemeError::Io(tmp.clone(), e))?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            ThemeError::Io(self.path.clone(), e)
+        })
+    }
+
+    /// Put back what was there before, as far as possible
+    fn undo(&self) {
+        let backup = self.path.with_file_name(BACKUP_NAME);
+        let result = if self.move_aside {
+            if exists(&backup) {
+                std::fs::rename(&backup, &self.path)
+            } else {
+                Ok(())
+            }
+        } else if let Some(previous) = &self.previous {
+            std::fs::write(&self.path, previous)
+        } else if exists(&self.path) {
+            std::fs::remove_file(&self.path)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            tracing::warn!("failed to roll back {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Pull the moved aside gtk.css in after the header comment, so its rules
+/// still apply and only the colors are ours
+fn import_backup(css: &str) -> String {
+    match css.split_once("\n\n") {
+        Some((header, rules)) => format!("{}\n\n@import url(\"{}\");\n\n{}", header, BACKUP_NAME, rules),
+        None => format!("@import url(\"{}\");\n{}", BACKUP_NAME, css),
+    }
+}
+
+/// Whether anything is at `path`, dangling symlinks included
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+fn is_ours(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .is_ok_and(|content| content.lines().next().is_some_and(|line| line.contains(MARKER)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_and_restores_gtk_css() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let gtk = GtkExport::with_config_dir(root.to_path_buf());
+        let [gtk3, gtk4] = [root.join("gtk-3.0/gtk.css"), root.join("gtk-4.0/gtk.css")];
+        std::fs::create_dir_all(gtk3.parent().unwrap()).unwrap();
+        std::fs::write(&gtk3, "/* mine */\n").unwrap();
+
+        let nord = crate::builtin_theme("nord").unwrap();
+        assert_eq!(gtk.write(&nord).unwrap(), [gtk3.clone(), gtk4.clone()]);
+        let css = std::fs::read_to_string(&gtk4).unwrap();
+        assert!(css.contains("@define-color accent_bg_color #88c0d0;"), "{}", css);
+        assert!(css.contains("@define-color window_bg_color #2e3440;"));
+        let css = std::fs::read_to_string(&gtk3).unwrap();
+        assert!(css.contains("@define-color theme_bg_color #2e3440;"));
+        assert!(css.contains("@import url(\"gtk.css.wonderland-backup\");"));
+        assert!(!std::fs::read_to_string(&gtk4).unwrap().contains("@import"));
+
+        // Switching again replaces our files and keeps the original backup
+        gtk.write(&crate::builtin_theme("gruvbox").unwrap()).unwrap();
+        let backup = std::fs::read_to_string(root.join("gtk-3.0").join(BACKUP_NAME)).unwrap();
+        assert_eq!(backup, "/* mine */\n");
+
+        gtk.restore().unwrap();
+        assert_eq!(std::fs::read_to_string(&gtk3).unwrap(), "/* mine */\n");
+        assert!(!exists(&gtk4));
+        assert!(!exists(&root.join("gtk-3.0").join(BACKUP_NAME)));
+    }
+
+    #[test]
+    fn failed_write_changes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let gtk = GtkExport::with_config_dir(root.to_path_buf());
+        let gtk3 = root.join("gtk-3.0/gtk.css");
+        std::fs::create_dir_all(gtk3.parent().unwrap()).unwrap();
+        std::fs::write(&gtk3, "/* mine */\n").unwrap();
+        // gtk-4.0 can't be created
+        std::fs::write(root.join("gtk-4.0"), "").unwrap();
+
+        assert!(gtk.write(&crate::builtin_theme("nord").unwrap()).is_err());
+        assert_eq!(std::fs::read_to_string(&gtk3).unwrap(), "/* mine */\n");
+        assert!(!exists(&root.join("gtk-3.0").join(BACKUP_NAME)));
+    }
+}
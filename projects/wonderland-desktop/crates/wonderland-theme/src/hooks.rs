@@ -0,0 +1,318 @@
+//! Reload hooks run after switching themes
+//!
+//! Hooks are shell commands declared in `~/.config/wonderland/theme-hooks.toml`:
+//!
+//! ```toml
+//! [[hook]]
+//! name = "waybar"
+//! command = "pkill -SIGUSR2 -x waybar || true"
+//!
+//! [[hook]]
+//! name = "hyprland"
+//! command = "hyprctl reload"
+//! timeout = 10
+//! ```
+//!
+//! Commands run through `sh -c` with `WONDERLAND_THEME` and
+//! `WONDERLAND_THEME_DIR` set. Every hook runs even if an earlier one fails,
+//! and each reports its own result.
+
+use crate::loader::ThemeError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Config file name, relative to the wonderland config dir
+const CONFIG_NAME: &str = "theme-hooks";
+
+/// How long a failed hook's stderr is still read after it exits
+const STDERR_GRACE: Duration = Duration::from_millis(200);
+
+/// A single reload step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReloadHook {
+    pub name: String,
+    /// Shell command, run with `sh -c`
+    pub command: String,
+    /// Seconds before the command is killed
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ReloadHook {
+    pub fn new(name: &str, command: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+            timeout: default_timeout(),
+            enabled: true,
+        }
+    }
+
+    /// Run the hook for `theme`, living in `theme_dir`
+    pub fn run(&self, theme: &str, theme_dir: &Path) -> HookReport {
+        let started = Instant::now();
+        let status = self.execute(theme, theme_dir);
+
+        HookReport {
+            name: self.name.clone(),
+            status,
+            duration: started.elapsed(),
+        }
+    }
+
+    fn execute(&self, theme: &str, theme_dir: &Path) -> HookStatus {
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("WONDERLAND_THEME", theme)
+            .env("WONDERLAND_THEME_DIR", theme_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => return HookStatus::SpawnFailed(e.to_string()),
+        };
+
+        // Drain stderr on a thread so a chatty command can't block on a full pipe
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let reader = child.stderr.take().map(|mut pipe| {
+            let stderr = Arc::clone(&stderr);
+            std::thread::spawn(move || {
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                    stderr.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&buf[..n]);
+                }
+            })
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(self.timeout);
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return HookStatus::TimedOut;
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+                Err(e) => return HookStatus::SpawnFailed(e.to_string()),
+            }
+        };
+
+        if status.success() {
+            return HookStatus::Ok;
+        }
+
+        // A process the command left in the background (`waybar &`) keeps
+        // the pipe open, so only wait a moment for the rest of stderr
+        let deadline = Instant::now() + STDERR_GRACE;
+        while reader.as_ref().is_some_and(|reader| !reader.is_finished()) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let stderr = stderr.lock().unwrap_or_else(|e| e.into_inner());
+        HookStatus::Failed {
+            code: status.code(),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        }
+    }
+}
+
+/// The hooks to run after a theme switch, in order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookRegistry {
+    #[serde(default, rename = "hook")]
+    pub hooks: Vec<ReloadHook>,
+}
+
+impl HookRegistry {
+    /// Load `theme-hooks.toml`, falling back to [`HookRegistry::defaults`]
+    /// when it doesn't exist
+    pub fn load() -> Result<Self, ThemeError> {
+        match wonderland_config::load(CONFIG_NAME) {
+            Ok(registry) => Ok(registry),
+            Err(wonderland_config::ConfigError::Io(_, e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::defaults())
+            }
+            Err(e) => Err(ThemeError::Config(Box::new(e))),
+        }
+    }
+
+    /// The steps the old `theme-set` script performed
+    pub fn defaults() -> Self {
+        Self {
+            hooks: vec![
+                ReloadHook::new("waybar", "pkill -SIGUSR2 -x waybar || true"),
+                ReloadHook::new("mako", "makoctl reload"),
+                ReloadHook::new("hyprland", "hyprctl reload"),
+                ReloadHook::new("terminals", "pkill -SIGUSR1 -x kitty || true"),
+                ReloadHook::new("btop", "pkill -SIGUSR2 -x btop || true"),
+                ReloadHook::new(
+                    "wallpaper",
+                    "bg=$(find \"$WONDERLAND_THEME_DIR/backgrounds/\" -type f | sort | head -n 1)\n\
+                     [ -n \"$bg\" ] || exit 0\n\
+                     pkill -x swaybg\n\
+                     setsid swaybg -i \"$bg\" -m fill >/dev/null 2>&1 &",
+                ),
+            ],
+        }
+    }
+
+    /// Add a hook at the end
+    pub fn with_hook(mut self, hook: ReloadHook) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Run every enabled hook, in order
+    pub fn run(&self, theme: &str, theme_dir: &Path) -> Vec<HookReport> {
+        self.hooks
+            .iter()
+            .filter(|hook| hook.enabled)
+            .map(|hook| {
+                let report = hook.run(theme, theme_dir);
+                if !report.status.is_ok() {
+                    tracing::warn!("reload hook {} failed: {}", report.name, report.status);
+                }
+                report
+            })
+            .collect()
+    }
+}
+
+/// Outcome of a single hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookStatus {
+    Ok,
+    Failed { code: Option<i32>, stderr: String },
+    TimedOut,
+    SpawnFailed(String),
+    /// A built-in step, like writing the GTK colors, failed
+    Error(String),
+}
+
+impl HookStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, HookStatus::Ok)
+    }
+}
+
+impl fmt::Display for HookStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookStatus::Ok => write!(f, "ok"),
+            HookStatus::Failed { code, stderr } => {
+                match code {
+                    Some(code) => write!(f, "exit status {}", code)?,
+                    None => write!(f, "killed by signal")?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+            HookStatus::TimedOut => write!(f, "timed out"),
+            HookStatus::SpawnFailed(e) => write!(f, "could not start: {}", e),
+            HookStatus::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Result of one hook run
+#[derive(Debug, Clone)]
+pub struct HookReport {
+    pub name: String,
+    pub status: HookStatus,
+    pub duration: Duration,
+}
+
+/// Result of [`ThemeLoader::set_current`](crate::ThemeLoader::set_current)
+#[derive(Debug, Clone)]
+pub struct SwitchReport {
+    pub theme: String,
+    /// Theme that was active before, if any
+    pub previous: Option<String>,
+    pub hooks: Vec<HookReport>,
+}
+
+impl SwitchReport {
+    /// Whether every hook succeeded
+    pub fn succeeded(&self) -> bool {
+        self.hooks.iter().all(|hook| hook.status.is_ok())
+    }
+
+    /// Hooks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &HookReport> {
+        self.hooks.iter().filter(|hook| !hook.status.is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_hook() {
+        let registry = HookRegistry::default()
+            .with_hook(ReloadHook::new("env", "test \"$WONDERLAND_THEME\" = nord"))
+            .with_hook(ReloadHook::new("broken", "echo oops >&2; exit 3"))
+            .with_hook(ReloadHook {
+                timeout: 0,
+                ..ReloadHook::new("slow", "sleep 5")
+            });
+
+        let reports = registry.run("nord", Path::new("/tmp"));
+
+        assert_eq!(reports[0].status, HookStatus::Ok);
+        assert_eq!(
+            reports[1].status,
+            HookStatus::Failed {
+                code: Some(3),
+                stderr: "oops".to_string()
+            }
+        );
+        assert_eq!(reports[2].status, HookStatus::TimedOut);
+    }
+
+    #[test]
+    fn background_processes_dont_hold_up_failures() {
+        let started = Instant::now();
+        let report = ReloadHook::new("waybar", "sleep 5 & echo oops >&2; false").run("nord", Path::new("/tmp"));
+
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+        assert_eq!(
+            report.status,
+            HookStatus::Failed {
+                code: Some(1),
+                stderr: "oops".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_config() {
+        let registry: HookRegistry =
+            toml::from_str("[[hook]]\nname = \"mako\"\ncommand = \"makoctl reload\"\nenabled = false\n")
+                .unwrap();
+        assert_eq!(registry.hooks[0].timeout, 5);
+        assert!(registry.run("nord", Path::new("/tmp")).is_empty());
+    }
+}
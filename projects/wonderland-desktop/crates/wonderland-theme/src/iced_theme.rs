@@ -1,18 +1,137 @@
 //! Iced theme implementation
 
 use crate::loader::Theme;
-use iced::widget::{button, container, scrollable, text, text_input};
-use iced::{Background, Border, Color};
+use crate::tokens::{Spacing, Tokens};
+use iced::application::{Appearance, DefaultStyle};
+use iced::overlay::menu;
+use iced::widget::{
+    button, checkbox, combo_box, container, pick_list, progress_bar, radio, rule, scrollable,
+    slider, svg, text, text_input, toggler,
+};
+use iced::{Background, Border, Color, Font, Shadow, Subscription, Vector};
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 /// Iced-compatible theme wrapper
 #[derive(Debug, Clone)]
 pub struct WonderlandTheme {
     pub theme: Theme,
+    duration: Duration,
+    easing: Easing,
+    transition: Option<Transition>,
+}
+
+/// Timing curve of theme transitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Starts slow
+    EaseIn,
+    /// Ends slow
+    EaseOut,
+    /// Starts and ends slow
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map linear progress (0.0-1.0) onto the curve
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// A theme change in progress
+#[derive(Debug, Clone)]
+struct Transition {
+    from: Theme,
+    to: Theme,
+    started: Instant,
 }
 
 impl WonderlandTheme {
     pub fn new(theme: Theme) -> Self {
-        Self { theme }
+        Self {
+            theme,
+            duration: Duration::ZERO,
+            easing: Easing::default(),
+            transition: None,
+        }
+    }
+
+    /// Cross-fade to new themes over `duration` instead of switching at
+    /// once. The app has to drive the animation: subscribe to
+    /// [`WonderlandTheme::frames`] and pass each frame to
+    /// [`WonderlandTheme::tick`].
+    pub fn with_transition(mut self, duration: Duration, easing: Easing) -> Self {
+        self.duration = duration;
+        self.easing = easing;
+        self
+    }
+
+    /// Switch to `theme`, animated if a transition is configured. A switch
+    /// during a transition starts from the colors currently shown.
+    pub fn set_theme(&mut self, theme: Theme, now: Instant) {
+        if self.duration.is_zero() {
+            self.theme = theme;
+            self.transition = None;
+            return;
+        }
+
+        self.transition = Some(Transition {
+            from: self.theme.clone(),
+            to: theme,
+            started: now,
+        });
+    }
+
+    /// Advance a running transition to `now`
+    pub fn tick(&mut self, now: Instant) {
+        let Some(transition) = &self.transition else {
+            return;
+        };
+
+        let progress = now.saturating_duration_since(transition.started).as_secs_f32()
+            / self.duration.as_secs_f32();
+        if progress >= 1.0 {
+            if let Some(transition) = self.transition.take() {
+                self.theme = transition.to;
+            }
+        } else {
+            self.theme = transition.from.lerp(&transition.to, self.easing.apply(progress));
+        }
+    }
+
+    /// Whether a transition is running
+    pub fn is_animating(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Theme a running transition ends at, or the current one
+    pub fn target(&self) -> &Theme {
+        self.transition.as_ref().map_or(&self.theme, |transition| &transition.to)
+    }
+
+    /// Redraw timestamps while a transition runs, for [`WonderlandTheme::tick`]
+    pub fn frames(&self) -> Subscription<Instant> {
+        if self.is_animating() {
+            iced::window::frames()
+        } else {
+            Subscription::none()
+        }
     }
 
     /// Get foreground as Iced color
@@ -34,6 +153,80 @@ impl WonderlandTheme {
     pub fn surface(&self) -> Color {
         self.theme.surface.to_iced()
     }
+
+    /// Design tokens of the theme
+    pub fn tokens(&self) -> &Tokens {
+        &self.theme.tokens
+    }
+
+    /// Spacing scale, for padding and gaps
+    pub fn spacing(&self) -> Spacing {
+        self.theme.tokens.spacing
+    }
+
+    /// Body text size
+    pub fn font_size(&self) -> f32 {
+        self.theme.tokens.font.size
+    }
+
+    /// UI font, for `Application::default_font` or `text().font()`
+    pub fn font(&self) -> Font {
+        self.theme
+            .tokens
+            .font
+            .family
+            .as_deref()
+            .map_or(Font::DEFAULT, |family| Font::with_name(intern(family)))
+    }
+
+    /// Monospace font
+    pub fn monospace_font(&self) -> Font {
+        self.theme
+            .tokens
+            .font
+            .monospace
+            .as_deref()
+            .map_or(Font::MONOSPACE, |family| Font::with_name(intern(family)))
+    }
+}
+
+impl Default for WonderlandTheme {
+    /// The default built-in theme
+    fn default() -> Self {
+        Self::new(crate::builtin::default_theme())
+    }
+}
+
+// Window background and default text color
+impl DefaultStyle for WonderlandTheme {
+    fn default_style(&self) -> Appearance {
+        Appearance {
+            background_color: self.background(),
+            text_color: self.foreground(),
+        }
+    }
+}
+
+/// Same color at a fraction of its opacity
+fn faded(color: Color, factor: f32) -> Color {
+    Color {
+        a: color.a * factor,
+        ..color
+    }
+}
+
+/// Iced fonts need `'static` names; leak each distinct family only once so
+/// hot reloads don't keep leaking
+fn intern(family: &str) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(name) = names.get(family) {
+        return name;
+    }
+    let name: &'static str = Box::leak(family.to_string().into_boxed_str());
+    names.insert(name);
+    name
 }
 
 // Container styling
@@ -56,11 +249,35 @@ impl container::Catalog for WonderlandTheme {
                 text_color: Some(self.foreground()),
                 border: Border {
                     color: self.theme.border.to_iced(),
-                    width: 1.0,
-                    radius: 8.0.into(),
+                    width: self.tokens().border.thin,
+                    radius: self.tokens().radius.large.into(),
                 },
-                ..Default::default()
+                shadow: self.tokens().shadow.to_iced(),
             },
+            ContainerClass::Elevated => {
+                // Popovers always cast a shadow, even if the theme sets none
+                let shadow = self.tokens().shadow.to_iced();
+                let shadow = if shadow.color.a > 0.0 {
+                    shadow
+                } else {
+                    Shadow {
+                        color: Color::from_rgba(0.0, 0.0, 0.0, 0.35),
+                        offset: Vector::new(0.0, 4.0),
+                        blur_radius: 16.0,
+                    }
+                };
+
+                container::Style {
+                    background: Some(Background::Color(self.surface())),
+                    text_color: Some(self.foreground()),
+                    border: Border {
+                        color: self.theme.border.to_iced(),
+                        width: self.tokens().border.thin,
+                        radius: self.tokens().radius.large.into(),
+                    },
+                    shadow,
+                }
+            }
             ContainerClass::Transparent => container::Style::default(),
         }
     }
@@ -71,6 +288,8 @@ pub enum ContainerClass {
     #[default]
     Default,
     Surface,
+    /// Surface floating above the content, with a shadow
+    Elevated,
     Transparent,
 }
 
@@ -88,7 +307,7 @@ impl button::Catalog for WonderlandTheme {
                 background: Some(Background::Color(self.primary())),
                 text_color: self.background(),
                 border: Border {
-                    radius: 6.0.into(),
+                    radius: self.tokens().radius.medium.into(),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -98,8 +317,26 @@ impl button::Catalog for WonderlandTheme {
                 text_color: self.foreground(),
                 border: Border {
                     color: self.theme.border.to_iced(),
-                    width: 1.0,
-                    radius: 6.0.into(),
+                    width: self.tokens().border.thin,
+                    radius: self.tokens().radius.medium.into(),
+                },
+                ..Default::default()
+            },
+            ButtonClass::Danger => button::Style {
+                background: Some(Background::Color(self.theme.error.to_iced())),
+                text_color: self.background(),
+                border: Border {
+                    radius: self.tokens().radius.medium.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ButtonClass::Success => button::Style {
+                background: Some(Background::Color(self.theme.success.to_iced())),
+                text_color: self.background(),
+                border: Border {
+                    radius: self.tokens().radius.medium.into(),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -115,10 +352,7 @@ impl button::Catalog for WonderlandTheme {
             button::Status::Hovered => button::Style {
                 background: base.background.map(|bg| {
                     if let Background::Color(c) = bg {
-                        Background::Color(Color {
-                            a: c.a * 0.9,
-                            ..c
-                        })
+                        Background::Color(faded(c, 0.9))
                     } else {
                         bg
                     }
@@ -128,10 +362,7 @@ impl button::Catalog for WonderlandTheme {
             button::Status::Pressed => button::Style {
                 background: base.background.map(|bg| {
                     if let Background::Color(c) = bg {
-                        Background::Color(Color {
-                            a: c.a * 0.7,
-                            ..c
-                        })
+                        Background::Color(faded(c, 0.7))
                     } else {
                         bg
                     }
@@ -141,10 +372,7 @@ impl button::Catalog for WonderlandTheme {
             button::Status::Disabled => button::Style {
                 background: base.background.map(|bg| {
                     if let Background::Color(c) = bg {
-                        Background::Color(Color {
-                            a: c.a * 0.5,
-                            ..c
-                        })
+                        Background::Color(faded(c, 0.5))
                     } else {
                         bg
                     }
@@ -164,6 +392,10 @@ pub enum ButtonClass {
     #[default]
     Primary,
     Secondary,
+    /// Destructive actions
+    Danger,
+    /// Confirming actions
+    Success,
     Text,
 }
 
@@ -189,6 +421,12 @@ impl text::Catalog for WonderlandTheme {
             TextClass::Error => text::Style {
                 color: Some(self.theme.error.to_iced()),
             },
+            TextClass::Warning => text::Style {
+                color: Some(self.theme.warning.to_iced()),
+            },
+            TextClass::Success => text::Style {
+                color: Some(self.theme.success.to_iced()),
+            },
         }
     }
 }
@@ -200,6 +438,8 @@ pub enum TextClass {
     Muted,
     Primary,
     Error,
+    Warning,
+    Success,
 }
 
 // Text input styling
@@ -215,8 +455,8 @@ impl text_input::Catalog for WonderlandTheme {
             background: Background::Color(self.surface()),
             border: Border {
                 color: self.theme.border.to_iced(),
-                width: 1.0,
-                radius: 6.0.into(),
+                width: self.tokens().border.thin,
+                radius: self.tokens().radius.medium.into(),
             },
             icon: self.theme.text_muted.to_iced(),
             placeholder: self.theme.text_muted.to_iced(),
@@ -236,7 +476,7 @@ impl text_input::Catalog for WonderlandTheme {
             text_input::Status::Focused => text_input::Style {
                 border: Border {
                     color: self.primary(),
-                    width: 2.0,
+                    width: self.tokens().border.thick,
                     ..base.border
                 },
                 ..base
@@ -273,7 +513,7 @@ impl scrollable::Catalog for WonderlandTheme {
             scroller: scrollable::Scroller {
                 color: self.theme.border.to_iced(),
                 border: Border {
-                    radius: 4.0.into(),
+                    radius: self.tokens().radius.small.into(),
                     ..Default::default()
                 },
             },
@@ -282,7 +522,7 @@ impl scrollable::Catalog for WonderlandTheme {
         match status {
             scrollable::Status::Active => scrollable::Style {
                 container: container::Style::default(),
-                vertical_rail: scrollbar.clone(),
+                vertical_rail: scrollbar,
                 horizontal_rail: scrollbar,
                 gap: None,
             },
@@ -295,15 +535,15 @@ impl scrollable::Catalog for WonderlandTheme {
                         color: self.theme.text_muted.to_iced(),
                         ..scrollbar.scroller
                     },
-                    ..scrollbar.clone()
+                    ..scrollbar
                 };
 
                 scrollable::Style {
                     container: container::Style::default(),
                     vertical_rail: if is_vertical_scrollbar_hovered {
-                        hovered_rail.clone()
+                        hovered_rail
                     } else {
-                        scrollbar.clone()
+                        scrollbar
                     },
                     horizontal_rail: if is_horizontal_scrollbar_hovered {
                         hovered_rail
@@ -322,15 +562,15 @@ impl scrollable::Catalog for WonderlandTheme {
                         color: self.primary(),
                         ..scrollbar.scroller
                     },
-                    ..scrollbar.clone()
+                    ..scrollbar
                 };
 
                 scrollable::Style {
                     container: container::Style::default(),
                     vertical_rail: if is_vertical_scrollbar_dragged {
-                        dragged_rail.clone()
+                        dragged_rail
                     } else {
-                        scrollbar.clone()
+                        scrollbar
                     },
                     horizontal_rail: if is_horizontal_scrollbar_dragged {
                         dragged_rail
@@ -349,3 +589,443 @@ pub enum ScrollableClass {
     #[default]
     Default,
 }
+
+// Checkbox styling
+impl checkbox::Catalog for WonderlandTheme {
+    type Class<'a> = CheckboxClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        CheckboxClass::Default
+    }
+
+    fn style(&self, _class: &Self::Class<'_>, status: checkbox::Status) -> checkbox::Style {
+        let (is_checked, hovered, disabled) = match status {
+            checkbox::Status::Active { is_checked } => (is_checked, false, false),
+            checkbox::Status::Hovered { is_checked } => (is_checked, true, false),
+            checkbox::Status::Disabled { is_checked } => (is_checked, false, true),
+        };
+
+        let (background, border) = match (is_checked, hovered) {
+            (true, false) => (self.primary(), self.primary()),
+            (true, true) => (faded(self.primary(), 0.9), self.primary()),
+            (false, false) => (self.surface(), self.theme.border.to_iced()),
+            (false, true) => (self.surface(), self.theme.border_active.to_iced()),
+        };
+        let style = checkbox::Style {
+            background: Background::Color(background),
+            icon_color: self.background(),
+            border: Border {
+                color: border,
+                width: self.tokens().border.thin,
+                radius: self.tokens().radius.small.into(),
+            },
+            text_color: None,
+        };
+
+        if disabled {
+            checkbox::Style {
+                background: Background::Color(faded(background, 0.5)),
+                border: Border {
+                    color: faded(border, 0.5),
+                    ..style.border
+                },
+                text_color: Some(self.theme.text_muted.to_iced()),
+                ..style
+            }
+        } else {
+            style
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum CheckboxClass {
+    #[default]
+    Default,
+}
+
+// Toggler styling
+impl toggler::Catalog for WonderlandTheme {
+    type Class<'a> = TogglerClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        TogglerClass::Default
+    }
+
+    fn style(&self, _class: &Self::Class<'_>, status: toggler::Status) -> toggler::Style {
+        let (track, knob) = match status {
+            toggler::Status::Active { is_toggled: true } => (self.primary(), self.background()),
+            toggler::Status::Hovered { is_toggled: true } => {
+                (faded(self.primary(), 0.9), self.background())
+            }
+            toggler::Status::Active { is_toggled: false } => {
+                (self.theme.border.to_iced(), self.foreground())
+            }
+            toggler::Status::Hovered { is_toggled: false } => {
+                (self.theme.border_active.to_iced(), self.foreground())
+            }
+            toggler::Status::Disabled => (self.surface(), self.theme.text_muted.to_iced()),
+        };
+
+        toggler::Style {
+            background: track,
+            background_border_width: 0.0,
+            background_border_color: Color::TRANSPARENT,
+            foreground: knob,
+            foreground_border_width: 0.0,
+            foreground_border_color: Color::TRANSPARENT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TogglerClass {
+    #[default]
+    Default,
+}
+
+// Slider styling
+impl slider::Catalog for WonderlandTheme {
+    type Class<'a> = SliderClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        SliderClass::Default
+    }
+
+    fn style(&self, _class: &Self::Class<'_>, status: slider::Status) -> slider::Style {
+        let handle = match status {
+            slider::Status::Active => self.primary(),
+            slider::Status::Hovered => self.theme.primary.lighten(0.05).to_iced(),
+            slider::Status::Dragged => self.theme.border_active.to_iced(),
+        };
+
+        slider::Style {
+            rail: slider::Rail {
+                backgrounds: (
+                    Background::Color(self.primary()),
+                    Background::Color(self.theme.border.to_iced()),
+                ),
+                width: 4.0,
+                border: Border {
+                    radius: self.tokens().radius.small.into(),
+                    ..Default::default()
+                },
+            },
+            handle: slider::Handle {
+                shape: slider::HandleShape::Circle { radius: 7.0 },
+                background: Background::Color(handle),
+                border_width: self.tokens().border.thin,
+                border_color: self.background(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum SliderClass {
+    #[default]
+    Default,
+}
+
+// Pick list styling
+impl pick_list::Catalog for WonderlandTheme {
+    type Class<'a> = PickListClass;
+
+    fn default<'a>() -> <Self as pick_list::Catalog>::Class<'a> {
+        PickListClass::Default
+    }
+
+    fn style(
+        &self,
+        _class: &<Self as pick_list::Catalog>::Class<'_>,
+        status: pick_list::Status,
+    ) -> pick_list::Style {
+        let base = pick_list::Style {
+            text_color: self.foreground(),
+            placeholder_color: self.theme.text_muted.to_iced(),
+            handle_color: self.theme.text_muted.to_iced(),
+            background: Background::Color(self.surface()),
+            border: Border {
+                color: self.theme.border.to_iced(),
+                width: self.tokens().border.thin,
+                radius: self.tokens().radius.medium.into(),
+            },
+        };
+
+        match status {
+            pick_list::Status::Active => base,
+            pick_list::Status::Hovered => pick_list::Style {
+                handle_color: self.foreground(),
+                border: Border {
+                    color: self.theme.border_active.to_iced(),
+                    ..base.border
+                },
+                ..base
+            },
+            pick_list::Status::Opened => pick_list::Style {
+                handle_color: self.primary(),
+                border: Border {
+                    color: self.primary(),
+                    ..base.border
+                },
+                ..base
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum PickListClass {
+    #[default]
+    Default,
+}
+
+// Dropdown menu styling, used by pick lists and combo boxes
+impl menu::Catalog for WonderlandTheme {
+    type Class<'a> = MenuClass;
+
+    fn default<'a>() -> <Self as menu::Catalog>::Class<'a> {
+        MenuClass::Default
+    }
+
+    fn style(&self, _class: &<Self as menu::Catalog>::Class<'_>) -> menu::Style {
+        menu::Style {
+            background: Background::Color(self.surface()),
+            border: Border {
+                color: self.theme.border.to_iced(),
+                width: self.tokens().border.thin,
+                radius: self.tokens().radius.medium.into(),
+            },
+            text_color: self.foreground(),
+            selected_text_color: self.background(),
+            selected_background: Background::Color(self.primary()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum MenuClass {
+    #[default]
+    Default,
+}
+
+// Combo boxes combine the text input and menu styles
+impl combo_box::Catalog for WonderlandTheme {}
+
+// Progress bar styling
+impl progress_bar::Catalog for WonderlandTheme {
+    type Class<'a> = ProgressBarClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        ProgressBarClass::Primary
+    }
+
+    fn style(&self, class: &Self::Class<'_>) -> progress_bar::Style {
+        let bar = match class {
+            ProgressBarClass::Primary => self.theme.primary,
+            ProgressBarClass::Success => self.theme.success,
+            ProgressBarClass::Warning => self.theme.warning,
+            ProgressBarClass::Danger => self.theme.error,
+        };
+
+        progress_bar::Style {
+            background: Background::Color(self.surface()),
+            bar: Background::Color(bar.to_iced()),
+            border: Border {
+                radius: self.tokens().radius.small.into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ProgressBarClass {
+    #[default]
+    Primary,
+    Success,
+    Warning,
+    Danger,
+}
+
+// Radio button styling
+impl radio::Catalog for WonderlandTheme {
+    type Class<'a> = RadioClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        RadioClass::Default
+    }
+
+    fn style(&self, _class: &Self::Class<'_>, status: radio::Status) -> radio::Style {
+        let border_color = match status {
+            radio::Status::Active { is_selected: true }
+            | radio::Status::Hovered { is_selected: true } => self.primary(),
+            radio::Status::Hovered { is_selected: false } => self.theme.border_active.to_iced(),
+            radio::Status::Active { is_selected: false } => self.theme.border.to_iced(),
+        };
+
+        radio::Style {
+            background: Background::Color(self.surface()),
+            dot_color: self.primary(),
+            border_width: self.tokens().border.thin,
+            border_color,
+            text_color: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RadioClass {
+    #[default]
+    Default,
+}
+
+// Divider styling
+impl rule::Catalog for WonderlandTheme {
+    type Class<'a> = RuleClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        RuleClass::Default
+    }
+
+    fn style(&self, class: &Self::Class<'_>) -> rule::Style {
+        let (color, width) = match class {
+            RuleClass::Default => (self.theme.border, self.tokens().border.thin),
+            RuleClass::Strong => (self.theme.border_active, self.tokens().border.thick),
+        };
+
+        rule::Style {
+            color: color.to_iced(),
+            width: width.round().max(1.0) as u16,
+            radius: 0.0.into(),
+            fill_mode: rule::FillMode::Full,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RuleClass {
+    #[default]
+    Default,
+    Strong,
+}
+
+// SVG icon styling
+impl svg::Catalog for WonderlandTheme {
+    type Class<'a> = SvgClass;
+
+    fn default<'a>() -> Self::Class<'a> {
+        SvgClass::Original
+    }
+
+    fn style(&self, class: &Self::Class<'_>, status: svg::Status) -> svg::Style {
+        let color = match (class, status) {
+            (SvgClass::Original, _) => None,
+            (SvgClass::Foreground, _) | (SvgClass::Muted, svg::Status::Hovered) => {
+                Some(self.foreground())
+            }
+            (SvgClass::Muted, svg::Status::Idle) => Some(self.theme.text_muted.to_iced()),
+            (SvgClass::Primary, _) => Some(self.primary()),
+        };
+
+        svg::Style { color }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum SvgClass {
+    /// The icon's own colors
+    #[default]
+    Original,
+    /// Tinted with the text color
+    Foreground,
+    /// Tinted muted, brightening on hover
+    Muted,
+    Primary,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::builtin_theme;
+    use iced::widget::{column, combo_box, text};
+    use iced::Element;
+
+    /// Compiles only if every widget can be styled by WonderlandTheme
+    #[test]
+    fn every_widget_uses_the_theme() {
+        let options = ["a", "b"];
+        let state = combo_box::State::new(options.to_vec());
+
+        let view: Element<'_, (), WonderlandTheme> = column![
+            text("text"),
+            button(text("delete")),
+            container(text("menu")),
+            checkbox("check", true).on_toggle(|_| ()),
+            toggler(false).on_toggle(|_| ()),
+            slider(0.0..=1.0, 0.5, |_| ()),
+            pick_list(options, Some("a"), |_| ()),
+            combo_box(&state, "search", None, |_| ()),
+            progress_bar(0.0..=1.0, 0.5),
+            radio("one", "a", Some("a"), |_| ()),
+            rule::Rule::horizontal(1),
+            text_input("input", ""),
+            scrollable(text("scroll")),
+        ]
+        .into();
+        drop(view);
+
+        let theme = WonderlandTheme::default();
+        assert_eq!(theme.default_style().background_color, theme.background());
+
+        let danger = button::Catalog::style(&theme, &ButtonClass::Danger, button::Status::Active);
+        assert_eq!(
+            danger.background,
+            Some(Background::Color(theme.theme.error.to_iced()))
+        );
+        let elevated = container::Catalog::style(&theme, &ContainerClass::Elevated);
+        assert!(elevated.shadow.color.a > 0.0);
+        let disabled = checkbox::Catalog::style(
+            &theme,
+            &CheckboxClass::Default,
+            checkbox::Status::Disabled { is_checked: true },
+        );
+        assert_eq!(disabled.text_color, Some(theme.theme.text_muted.to_iced()));
+    }
+
+    #[test]
+    fn transitions_cross_fade() {
+        let dark = builtin_theme("catppuccin").unwrap();
+        let light = builtin_theme("catppuccin-latte").unwrap();
+        let start = Instant::now();
+
+        let mut theme = WonderlandTheme::new(dark.clone()).with_transition(Duration::from_millis(200), Easing::Linear);
+        theme.set_theme(light.clone(), start);
+        assert!(theme.is_animating());
+        assert_eq!(theme.target().name, "catppuccin-latte");
+
+        theme.tick(start + Duration::from_millis(100));
+        let halfway = dark.background.mix(&light.background, 0.5);
+        assert_eq!(theme.theme.background.to_hex(), halfway.to_hex());
+
+        // What an app hands to iced mid-transition draws the blended colors
+        let drawn = theme.clone();
+        assert_eq!(drawn.default_style().background_color, theme.theme.background.to_iced());
+        let surface = container::Catalog::style(&drawn, &ContainerClass::Surface);
+        assert_eq!(surface.background, Some(Background::Color(theme.theme.surface.to_iced())));
+        assert_ne!(surface.background, Some(Background::Color(light.surface.to_iced())));
+
+        theme.tick(start + Duration::from_millis(250));
+        assert!(!theme.is_animating());
+        assert_eq!(theme.theme.background, light.background);
+
+        // Without a transition the switch is immediate
+        let mut instant = WonderlandTheme::new(dark);
+        instant.set_theme(light.clone(), start);
+        assert!(!instant.is_animating());
+        assert_eq!(instant.theme.primary, light.primary);
+
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25 && Easing::EaseOut.apply(0.25) > 0.25);
+    }
+}
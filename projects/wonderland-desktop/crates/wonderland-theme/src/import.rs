@@ -0,0 +1,396 @@
+//! Import color schemes from other ecosystems
+//!
+//! Supports base16/base24 YAML schemes, Alacritty TOML, kitty.conf and
+//! iTerm2 `.itermcolors` files. Each is mapped onto Wonderland roles and
+//! written out as a full theme directory with the [`Exporter`].
+
+use crate::color::Color;
+use crate::export::Exporter;
+use crate::loader::{validate_name, Role, Theme, ThemeError, ThemeLoader};
+use crate::terminal::{self, TerminalColors, TerminalPalette};
+use crate::theme_file::Variant;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Source format of a scheme file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeFormat {
+    /// base16 or base24 YAML (classic flat or tinted-theming `palette:` layout)
+    Base16,
+    Alacritty,
+    Kitty,
+    Iterm2,
+}
+
+impl SchemeFormat {
+    /// Guess the format from a file extension
+    pub fn detect(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(SchemeFormat::Base16),
+            "toml" => Some(SchemeFormat::Alacritty),
+            "conf" => Some(SchemeFormat::Kitty),
+            "itermcolors" => Some(SchemeFormat::Iterm2),
+            _ => None,
+        }
+    }
+}
+
+/// A color scheme mapped onto Wonderland roles
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    pub name: String,
+    pub author: Option<String>,
+    pub variant: Option<Variant>,
+    pub foreground: Color,
+    pub background: Color,
+    /// Roles the scheme defines; the rest are derived
+    pub roles: BTreeMap<Role, Color>,
+    pub terminal: TerminalColors,
+}
+
+impl Scheme {
+    /// Parse a scheme file
+    pub fn load(path: &Path, format: SchemeFormat) -> Result<Self, ThemeError> {
+        let content = std::fs::read(path).map_err(|e| ThemeError::Io(path.to_path_buf(), e))?;
+        let fail = |message: String| ThemeError::Import(path.to_path_buf(), message);
+
+        let mut scheme = match format {
+            SchemeFormat::Iterm2 => parse_iterm2(&content).map_err(fail)?,
+            _ => {
+                let text = String::from_utf8(content).map_err(|e| fail(e.to_string()))?;
+                match format {
+                    SchemeFormat::Base16 => parse_base16(&text),
+                    SchemeFormat::Alacritty => parse_alacritty(&text),
+                    _ => parse_kitty(&text),
+                }
+                .map_err(fail)?
+            }
+        };
+
+        // Fall back to the file name for formats without a name field
+        if scheme.name.is_empty() {
+            scheme.name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("imported")
+                .to_string();
+        }
+
+        Ok(scheme)
+    }
+
+    /// Build a theme: derive from foreground/background, then apply the
+    /// roles the scheme defines
+    pub fn to_theme(&self, theme_name: &str) -> Theme {
+        let mut theme = Theme::derive_extended_colors(self.foreground, self.background);
+        for (role, color) in &self.roles {
+            *theme.color_mut(*role) = *color;
+        }
+        theme.terminal = TerminalPalette::derive(&theme);
+        self.terminal.apply(&mut theme.terminal);
+
+        theme.name = theme_name.to_string();
+        theme.meta.display_name = self.name.clone();
+        theme.meta.author = self.author.clone();
+        if let Some(variant) = self.variant {
+            theme.meta.variant = variant;
+        }
+        theme
+    }
+}
+
+/// Import a scheme file as a new theme directory under the loader's
+/// themes dir. Returns the new theme's name.
+pub fn import(
+    loader: &ThemeLoader,
+    path: &Path,
+    format: Option<SchemeFormat>,
+    name: Option<&str>,
+    overwrite: bool,
+) -> Result<String, ThemeError> {
+    let format = format
+        .or_else(|| SchemeFormat::detect(path))
+        .ok_or_else(|| ThemeError::Import(path.to_path_buf(), "unknown scheme format".to_string()))?;
+
+    let scheme = Scheme::load(path, format)?;
+    let theme_name = name.map(str::to_string).unwrap_or_else(|| slugify(&scheme.name));
+    validate_name(&theme_name)?;
+
+    let dest = loader.theme_dir(&theme_name);
+    if dest.exists() && !overwrite {
+        return Err(ThemeError::AlreadyExists(theme_name));
+    }
+
+    let mut theme = scheme.to_theme(&theme_name);
+    theme.path = dest.clone();
+    Exporter::new()?.export(&theme, &dest)?;
+
+    Ok(theme_name)
+}
+
+/// Turn a display name into a directory name: "Tokyo Night" -> "tokyo-night"
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Parse a required color value
+fn required(value: Option<&str>, key: &str) -> Result<Color, String> {
+    value
+        .map(terminal::parse_color)
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| format!("missing {}", key))
+}
+
+/// Roles for a scheme with a standard 16-color ANSI palette
+fn ansi_roles(terminal: &TerminalColors) -> BTreeMap<Role, Color> {
+    [
+        (1, Role::Error),
+        (2, Role::Success),
+        (3, Role::Warning),
+        (4, Role::Primary),
+        (4, Role::BorderActive),
+        (5, Role::Secondary),
+        (8, Role::TextMuted),
+    ]
+    .into_iter()
+    .filter_map(|(index, role)| terminal.colors[index].map(|color| (role, color)))
+    .collect()
+}
+
+/// base16 slots for color0-color15. base24 schemes replace the bright
+/// colors with base12-base17.
+const BASE16_ANSI: [&str; 16] = [
+    "base00", "base08", "base0B", "base0A", "base0D", "base0E", "base0C", "base05",
+    "base03", "base08", "base0B", "base0A", "base0D", "base0E", "base0C", "base07",
+];
+const BASE24_BRIGHT: [(usize, &str); 6] = [
+    (9, "base12"),
+    (10, "base14"),
+    (11, "base13"),
+    (12, "base16"),
+    (13, "base17"),
+    (14, "base15"),
+];
+
+fn parse_base16(text: &str) -> Result<Scheme, String> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    let field = |key: &str| doc.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    // tinted-theming nests the slots under `palette:`, classic files don't
+    let slots = doc.get("palette").unwrap_or(&doc);
+    let raw = |key: &str| slots.get(key).and_then(|v| v.as_str());
+    let slot = |key: &str| raw(key).map(terminal::parse_color).transpose().map(Option::flatten);
+
+    let mut roles = BTreeMap::new();
+    for (key, role) in [
+        ("base01", Role::Surface),
+        ("base02", Role::Border),
+        ("base04", Role::TextMuted),
+        ("base08", Role::Error),
+        ("base0A", Role::Warning),
+        ("base0B", Role::Success),
+        ("base0D", Role::Primary),
+        ("base0D", Role::BorderActive),
+        ("base0E", Role::Secondary),
+    ] {
+        if let Some(color) = slot(key)? {
+            roles.insert(role, color);
+        }
+    }
+
+    let mut terminal = TerminalColors::default();
+    for (i, key) in BASE16_ANSI.iter().enumerate() {
+        terminal.colors[i] = slot(key)?;
+    }
+    for (i, key) in BASE24_BRIGHT {
+        if let Some(color) = slot(key)? {
+            terminal.colors[i] = Some(color);
+        }
+    }
+    terminal.cursor = slot("base05")?;
+    terminal.cursor_text = slot("base00")?;
+    terminal.selection_foreground = slot("base05")?;
+    terminal.selection_background = slot("base02")?;
+
+    Ok(Scheme {
+        name: field("name").or_else(|| field("scheme")).unwrap_or_default(),
+        author: field("author"),
+        variant: match field("variant").as_deref() {
+            Some("light") => Some(Variant::Light),
+            Some("dark") => Some(Variant::Dark),
+            _ => None,
+        },
+        foreground: required(raw("base05"), "base05")?,
+        background: required(raw("base00"), "base00")?,
+        roles,
+        terminal,
+    })
+}
+
+fn parse_alacritty(text: &str) -> Result<Scheme, String> {
+    let doc: toml::Value = toml::from_str(text).map_err(|e| e.to_string())?;
+    let colors = doc.get("colors").ok_or("missing [colors] section")?;
+    let primary = |key: &str| {
+        colors
+            .get("primary")
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_str())
+    };
+
+    let terminal = TerminalColors::from_alacritty(colors)?;
+    Ok(Scheme {
+        name: String::new(),
+        author: None,
+        variant: None,
+        foreground: required(primary("foreground"), "colors.primary.foreground")?,
+        background: required(primary("background"), "colors.primary.background")?,
+        roles: ansi_roles(&terminal),
+        terminal,
+    })
+}
+
+fn parse_kitty(text: &str) -> Result<Scheme, String> {
+    let entries = terminal::kitty_entries(text);
+    let value = |key: &str| entries.get(key).map(String::as_str);
+
+    let terminal = TerminalColors::from_kitty(&entries)?;
+    let mut roles = ansi_roles(&terminal);
+    for (key, role) in [
+        ("active_border_color", Role::BorderActive),
+        ("inactive_border_color", Role::Border),
+    ] {
+        if let Some(color) = value(key).map(terminal::parse_color).transpose()?.flatten() {
+            roles.insert(role, color);
+        }
+    }
+
+    Ok(Scheme {
+        name: String::new(),
+        author: None,
+        variant: None,
+        foreground: required(value("foreground"), "foreground")?,
+        background: required(value("background"), "background")?,
+        roles,
+        terminal,
+    })
+}
+
+fn parse_iterm2(content: &[u8]) -> Result<Scheme, String> {
+    let doc = plist::Value::from_reader(std::io::Cursor::new(content)).map_err(|e| e.to_string())?;
+    let dict = doc.as_dictionary().ok_or("expected a plist dictionary")?;
+
+    let color = |key: &str| -> Option<Color> {
+        let entry = dict.get(key)?.as_dictionary()?;
+        let component = |name: &str| {
+            entry
+                .get(name)
+                .and_then(|v| v.as_real())
+                .map(|v| v as f32)
+        };
+        Some(Color::new(
+            component("Red Component")?,
+            component("Green Component")?,
+            component("Blue Component")?,
+            component("Alpha Component").unwrap_or(1.0),
+        ))
+    };
+
+    let mut terminal = TerminalColors::default();
+    for (i, slot) in terminal.colors.iter_mut().enumerate() {
+        *slot = color(&format!("Ansi {} Color", i));
+    }
+    terminal.cursor = color("Cursor Color");
+    terminal.cursor_text = color("Cursor Text Color");
+    terminal.selection_foreground = color("Selected Text Color");
+    terminal.selection_background = color("Selection Color");
+
+    Ok(Scheme {
+        name: String::new(),
+        author: None,
+        variant: None,
+        foreground: color("Foreground Color").ok_or("missing Foreground Color")?,
+        background: color("Background Color").ok_or("missing Background Color")?,
+        roles: ansi_roles(&terminal),
+        terminal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base16_both_layouts() {
+        let classic = parse_base16(
+            "scheme: \"Tokyo Night\"\nauthor: \"enkia\"\nbase00: \"1a1b26\"\nbase05: \"c0caf5\"\nbase0D: \"7aa2f7\"\n",
+        )
+        .unwrap();
+        assert_eq!(classic.name, "Tokyo Night");
+        assert_eq!(classic.roles[&Role::Primary].to_hex(), "#7aa2f7");
+
+        let tinted = parse_base16(
+            "system: \"base24\"\nname: \"Latte\"\nvariant: \"light\"\npalette:\n  base00: \"#eff1f5\"\n  base05: \"#4c4f69\"\n",
+        )
+        .unwrap();
+        assert_eq!(tinted.variant, Some(Variant::Light));
+        assert_eq!(tinted.terminal.colors[0], Some(tinted.background));
+        assert_eq!(tinted.background.to_hex(), "#eff1f5");
+    }
+
+    #[test]
+    fn terminal_formats() {
+        let alacritty = parse_alacritty(
+            "[colors.primary]\nbackground = '0x282828'\nforeground = '#ebdbb2'\n[colors.normal]\nred = '#cc241d'\n",
+        )
+        .unwrap();
+        assert_eq!(alacritty.background.to_hex(), "#282828");
+        assert_eq!(alacritty.roles[&Role::Error].to_hex(), "#cc241d");
+
+        let kitty = parse_kitty("# comment\nforeground #c0caf5\nbackground #1a1b26\ncolor4 #7aa2f7\n").unwrap();
+        assert_eq!(kitty.roles[&Role::Primary].to_hex(), "#7aa2f7");
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Tokyo Night (Storm)"), "tokyo-night-storm");
+    }
+
+    #[test]
+    fn malformed_colors_are_errors() {
+        assert!(parse_base16("scheme: \"x\"\nbase00: \"#é12\"\nbase05: \"c0caf5\"\n").is_err());
+        assert!(parse_alacritty("[colors.primary]\nbackground = '#é12'\nforeground = '#ebdbb2'\n").is_err());
+        assert!(parse_kitty("foreground #c0caf5\nbackground #1a1b26\ncolor4 #é12\n").is_err());
+
+        let root = tempfile::tempdir().unwrap();
+        let loader = ThemeLoader::with_paths(root.path().join("themes"), root.path().join("current/theme"));
+        let scheme = root.path().join("scheme.yaml");
+        std::fs::write(&scheme, "scheme: \"x\"\nbase00: \"#é12\"\nbase05: \"c0caf5\"\n").unwrap();
+        let err = import(&loader, &scheme, Some(SchemeFormat::Base16), None, false).unwrap_err();
+        assert!(matches!(err, ThemeError::Import(..)), "{}", err);
+    }
+
+    #[test]
+    fn rejects_names_outside_themes_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let themes = root.path().join("themes");
+        let loader = ThemeLoader::with_paths(themes.clone(), root.path().join("current/theme"));
+        let scheme = root.path().join("scheme.yaml");
+        std::fs::write(&scheme, "scheme: \"東京\"\nbase00: \"1a1b26\"\nbase05: \"c0caf5\"\n").unwrap();
+
+        for name in [None, Some("../x")] {
+            let err = import(&loader, &scheme, Some(SchemeFormat::Base16), name, false).unwrap_err();
+            assert!(matches!(err, ThemeError::InvalidName(_)), "{:?}: {}", name, err);
+        }
+        assert!(!themes.exists());
+        assert!(!root.path().join("x").exists());
+    }
+}
@@ -1,17 +1,67 @@
 //! Wonderland Theme System
 //!
 //! Loads themes from ~/.config/theme/themes/ and provides Iced theming.
+//! A few built-in themes are compiled in for machines without any installed.
+//! Use [`watcher::subscription`] to restyle an Iced app when the theme changes.
+//!
+//! The Iced parts are behind the default `iced` feature; non-Iced apps can
+//! turn it off and still load and watch themes. The `wonderland-theme`
+//! command line tool is behind the `cli` feature:
+//! `cargo build -p wonderland-theme --features cli`.
 
+mod audit;
+mod builtin;
 mod color;
+mod css;
+mod hooks;
 mod loader;
+mod selection;
+mod terminal;
+mod theme_file;
+mod tokens;
+pub mod export;
+pub mod generate;
+pub mod gtk;
+#[cfg(feature = "iced")]
 pub mod iced_theme;
+pub mod import;
+pub mod lint;
+pub mod package;
+pub mod preview;
+pub mod schedule;
+pub mod watcher;
 
-pub use color::Color;
-pub use loader::{Theme, ThemeLoader, ThemeError};
-pub use iced_theme::{WonderlandTheme, ContainerClass, ButtonClass, TextClass, TextInputClass};
+pub use audit::{ContrastCheck, ContrastReport, Correction, WcagLevel};
+pub use builtin::{builtin_names, builtin_theme, default_theme, BUILTIN_THEMES, DEFAULT_BUILTIN};
+pub use color::{Color, ColorError, Hsl, Hsv, Oklab, Oklch};
+pub use css::{parse_define_colors, CssError};
+pub use export::Exporter;
+pub use generate::{Generator, Swatch};
+pub use gtk::GtkExport;
+pub use hooks::{HookRegistry, HookReport, HookStatus, ReloadHook, SwitchReport};
+pub use import::{Scheme, SchemeFormat};
+pub use loader::{Role, Theme, ThemeLoader, ThemeError};
+pub use lint::{Issue, IssueKind, LintReport, Linter, Severity, ThemeLint};
+pub use package::{Manifest, Package};
+pub use preview::Preview;
+pub use schedule::{Schedule, Scheduler, Transition, Trigger};
+pub use selection::{theme_name_from_path, SelectionBackend, NIX_STORE};
+pub use terminal::{TerminalColors, TerminalPalette, ANSI_NAMES};
+pub use tokens::{BorderWidths, Radii, Shadow, Spacing, Tokens, Typography};
+pub use theme_file::{ColorOverrides, MetaSection, TerminalSection, ThemeFile, ThemeMeta, Variant};
+pub use watcher::ThemeWatcher;
+#[cfg(feature = "iced")]
+pub use iced_theme::{
+    WonderlandTheme, Easing, ContainerClass, ButtonClass, TextClass, TextInputClass, ScrollableClass,
+    CheckboxClass, TogglerClass, SliderClass, PickListClass, MenuClass, ProgressBarClass,
+    RadioClass, RuleClass, SvgClass,
+};
 
 /// Default theme directory
 pub const THEME_DIR: &str = ".config/theme/themes";
 
 /// Current theme symlink location
 pub const CURRENT_THEME: &str = ".config/theme/current/theme";
+
+/// Selection state file, relative to the wonderland state dir
+pub const SELECTION_STATE: &str = "current-theme.toml";
@@ -0,0 +1,595 @@
+//! Theme directory linting
+//!
+//! Checks installed themes for the problems that otherwise only show up
+//! when switching to them: missing app files, colors the apps can't parse,
+//! a palette that fails contrast, and app files that disagree about what
+//! color a role is (say, waybar.css and kitty.conf using different
+//! backgrounds).
+
+use crate::audit::WcagLevel;
+use crate::color::Color;
+use crate::css::{self, CssError};
+use crate::loader::{Role, ThemeError, ThemeLoader};
+use crate::terminal::TerminalColors;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Files every theme has to ship; the apps fail to start without them
+const REQUIRED_FILES: &[&str] = &["waybar.css", "hyprland.conf", "mako.ini"];
+
+/// Extensions of the app files scanned for colors
+const CONFIG_EXTENSIONS: &[&str] = &["css", "conf", "ini", "toml", "theme"];
+
+/// Keys holding a role's color, per file, as rendered by the built-in
+/// templates. Keys in a `[section]` are prefixed with `section.`.
+const ROLE_KEYS: &[(&str, &str, Role)] = &[
+    ("waybar.css", "foreground", Role::Foreground),
+    ("waybar.css", "background", Role::Background),
+    ("waybar.css", "primary", Role::Primary),
+    ("waybar.css", "surface", Role::Surface),
+    ("waybar.css", "border", Role::Border),
+    ("waybar.css", "muted", Role::TextMuted),
+    ("waybar.css", "error", Role::Error),
+    ("waybar.css", "warning", Role::Warning),
+    ("waybar.css", "success", Role::Success),
+    ("walker.css", "text", Role::Foreground),
+    ("walker.css", "base", Role::Background),
+    ("walker.css", "selected-text", Role::Primary),
+    ("walker.css", "border", Role::BorderActive),
+    ("mako.ini", "text-color", Role::Foreground),
+    ("mako.ini", "background-color", Role::Background),
+    ("mako.ini", "border-color", Role::BorderActive),
+    ("hyprland.conf", "col.active_border", Role::BorderActive),
+    ("hyprland.conf", "col.inactive_border", Role::Border),
+    ("hyprlock.conf", "$color", Role::Background),
+    ("hyprlock.conf", "$font_color", Role::Foreground),
+    ("hyprlock.conf", "$outer_color", Role::BorderActive),
+    ("alacritty.toml", "colors.primary.foreground", Role::Foreground),
+    ("alacritty.toml", "colors.primary.background", Role::Background),
+    ("kitty.conf", "foreground", Role::Foreground),
+    ("kitty.conf", "background", Role::Background),
+    ("kitty.conf", "active_border_color", Role::BorderActive),
+    ("kitty.conf", "inactive_border_color", Role::Border),
+    ("ghostty.conf", "foreground", Role::Foreground),
+    ("ghostty.conf", "background", Role::Background),
+    ("btop.theme", "theme[main_fg]", Role::Foreground),
+    ("btop.theme", "theme[main_bg]", Role::Background),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The theme doesn't load at all
+    Load,
+    MissingFile,
+    InvalidColor,
+    Contrast,
+    /// App files use different colors for the same role
+    Inconsistent,
+}
+
+/// One problem found in a theme
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    /// File within the theme directory
+    pub file: Option<String>,
+    /// 1-based line in `file`
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file, self.message),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Issues of one theme
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeLint {
+    pub theme: String,
+    pub path: PathBuf,
+    pub issues: Vec<Issue>,
+}
+
+impl ThemeLint {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == severity).count()
+    }
+}
+
+/// Issues of every linted theme
+#[derive(Debug, Clone, Serialize)]
+pub struct LintReport {
+    pub themes: Vec<ThemeLint>,
+}
+
+impl LintReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.themes.iter().map(|theme| theme.count(severity)).sum()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+/// Lints theme directories
+#[derive(Debug, Clone, Copy)]
+pub struct Linter {
+    level: WcagLevel,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Requires WCAG AA contrast
+    pub fn new() -> Self {
+        Self { level: WcagLevel::Aa }
+    }
+
+    /// Contrast level the palette has to reach
+    pub fn with_level(mut self, level: WcagLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Lint every installed theme
+    pub fn lint_all(&self, loader: &ThemeLoader) -> Result<LintReport, crate::ThemeError> {
+        let themes = loader
+            .list_themes()?
+            .iter()
+            .map(|name| self.lint(loader, name))
+            .collect();
+        Ok(LintReport { themes })
+    }
+
+    /// Lint one installed theme
+    pub fn lint(&self, loader: &ThemeLoader, name: &str) -> ThemeLint {
+        let dir = loader.theme_dir(name);
+        let mut issues = Vec::new();
+
+        if !dir.is_dir() {
+            issues.push(Issue {
+                severity: Severity::Error,
+                kind: IssueKind::Load,
+                file: None,
+                line: None,
+                message: format!("no theme directory at {}", dir.display()),
+            });
+            return ThemeLint {
+                theme: name.to_string(),
+                path: dir,
+                issues,
+            };
+        }
+
+        missing_files(&dir, &mut issues);
+        let colors = scan_files(&dir, &mut issues);
+        terminal_palette(&dir, &mut issues);
+        inconsistent_roles(&colors, &mut issues);
+
+        match loader.load_theme(name) {
+            Ok(theme) => {
+                for check in theme.audit().failures(self.level) {
+                    issues.push(Issue {
+                        severity: Severity::Warning,
+                        kind: IssueKind::Contrast,
+                        file: None,
+                        line: None,
+                        message: format!(
+                            "{} ({} on {}) has contrast {:.2}:1, below {} ({}:1)",
+                            check.context,
+                            check.foreground,
+                            check.background,
+                            check.ratio,
+                            match self.level {
+                                WcagLevel::AaLarge => "AA large text",
+                                WcagLevel::Aa => "AA",
+                                WcagLevel::Aaa => "AAA",
+                            },
+                            self.level.min_ratio()
+                        ),
+                    });
+                }
+            }
+            Err(e) => issues.push(Issue {
+                severity: Severity::Error,
+                kind: IssueKind::Load,
+                file: None,
+                line: None,
+                message: e.to_string(),
+            }),
+        }
+
+        ThemeLint {
+            theme: name.to_string(),
+            path: dir,
+            issues,
+        }
+    }
+}
+
+fn missing_files(dir: &Path, issues: &mut Vec<Issue>) {
+    for file in REQUIRED_FILES {
+        if !dir.join(file).is_file() {
+            issues.push(Issue {
+                severity: Severity::Error,
+                kind: IssueKind::MissingFile,
+                file: Some(file.to_string()),
+                line: None,
+                message: "missing".to_string(),
+            });
+        }
+    }
+
+    let has_backgrounds = std::fs::read_dir(dir.join("backgrounds"))
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if !has_backgrounds {
+        issues.push(Issue {
+            severity: Severity::Warning,
+            kind: IssueKind::MissingFile,
+            file: Some("backgrounds/".to_string()),
+            line: None,
+            message: "missing or empty, the wallpaper won't change".to_string(),
+        });
+    }
+}
+
+/// A role's color as one file sets it
+struct RoleColor {
+    role: Role,
+    color: Color,
+    file: String,
+    line: u32,
+}
+
+/// The terminal palette file, which the loader skips when it doesn't parse.
+/// Bad colors in it are already reported by the file scan.
+fn terminal_palette(dir: &Path, issues: &mut Vec<Issue>) {
+    let (file, message) = match TerminalColors::from_theme_dir(dir) {
+        Ok(_) => return,
+        Err(ThemeError::Terminal(path, message)) => {
+            let file = path.file_name().map(|name| name.to_string_lossy().into_owned());
+            (file, message)
+        }
+        Err(e) => (None, e.to_string()),
+    };
+    if issues.iter().any(|issue| issue.severity == Severity::Error && issue.file == file) {
+        return;
+    }
+    issues.push(Issue {
+        severity: Severity::Error,
+        kind: IssueKind::InvalidColor,
+        file,
+        line: None,
+        message: format!("terminal palette not applied: {}", message),
+    });
+}
+
+/// Check the colors of every app file, collecting the ones that set a role
+fn scan_files(dir: &Path, issues: &mut Vec<Issue>) -> Vec<RoleColor> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| CONFIG_EXTENSIONS.contains(&ext))
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    let mut colors = Vec::new();
+    for path in files {
+        let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    kind: IssueKind::Load,
+                    file: Some(file),
+                    line: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let entries = if file.ends_with(".css") {
+            css_colors(&file, &content, issues)
+        } else {
+            config_colors(&file, &content, issues)
+        };
+        for (key, line, color) in entries {
+            if let Some(&(_, _, role)) = ROLE_KEYS.iter().find(|(f, k, _)| *f == file && *k == key) {
+                colors.push(RoleColor {
+                    role,
+                    color,
+                    file: file.clone(),
+                    line,
+                });
+            }
+        }
+    }
+
+    colors
+}
+
+/// `@define-color`s of a stylesheet as (name, line, color)
+fn css_colors(file: &str, content: &str, issues: &mut Vec<Issue>) -> Vec<(String, u32, Color)> {
+    // The parser stops at the first syntax error, so blank out each bad
+    // line and go again to find the rest
+    let mut lines: Vec<&str> = content.lines().collect();
+    let defined = loop {
+        match css::define_colors(&lines.join("\n")) {
+            Ok(result) => break result,
+            Err(CssError::Syntax { line, column, message }) if (line as usize) <= lines.len() => {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    kind: IssueKind::InvalidColor,
+                    file: Some(file.to_string()),
+                    line: Some(line),
+                    message: format!("column {}: {}", column, message),
+                });
+                lines[line as usize - 1] = "";
+            }
+            Err(e) => {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    kind: IssueKind::InvalidColor,
+                    file: Some(file.to_string()),
+                    line: None,
+                    message: e.to_string(),
+                });
+                return Vec::new();
+            }
+        }
+    };
+
+    for (name, missing) in defined.undefined {
+        issues.push(Issue {
+            severity: Severity::Warning,
+            kind: IssueKind::InvalidColor,
+            file: Some(file.to_string()),
+            line: define_line(content, &name),
+            message: format!("@{} references undefined color @{}", name, missing),
+        });
+    }
+
+    defined
+        .colors
+        .into_iter()
+        .filter_map(|(name, color)| define_line(content, &name).map(|line| (name, line, color)))
+        .collect()
+}
+
+/// Line of the last `@define-color name`, the one that wins
+fn define_line(content: &str, name: &str) -> Option<u32> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let mut words = line.split_whitespace();
+            words.next() == Some("@define-color") && words.next() == Some(name)
+        })
+        .last()
+        .map(|(i, _)| i as u32 + 1)
+}
+
+/// Colors of a `key = value` / `key value` config as (key, line, color).
+/// Every `#hex` and `rgb(a)(hex)` value is checked; values of role keys
+/// may also be bare hex, like ghostty's.
+fn config_colors(file: &str, content: &str, issues: &mut Vec<Issue>) -> Vec<(String, u32, Color)> {
+    let mut colors = Vec::new();
+    let mut section = String::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i as u32 + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+        };
+        let key = if section.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", section, key)
+        };
+
+        let mut literals = color_literals(value);
+        let is_role_key = ROLE_KEYS.iter().any(|(f, k, _)| *f == file && *k == key);
+        if literals.is_empty() && is_role_key {
+            literals.push(value.trim_matches('"').trim());
+        }
+
+        for literal in literals {
+            match Color::from_hex(literal) {
+                Ok(color) => colors.push((key.clone(), line_number, color)),
+                Err(e) => issues.push(Issue {
+                    severity: Severity::Error,
+                    kind: IssueKind::InvalidColor,
+                    file: Some(file.to_string()),
+                    line: Some(line_number),
+                    message: format!("invalid color `{}` for {}: {}", literal, key, e),
+                }),
+            }
+        }
+    }
+
+    colors
+}
+
+/// `#hex` words and the hex inside `rgb()`/`rgba()`
+fn color_literals(value: &str) -> Vec<&str> {
+    let mut literals = Vec::new();
+    let word_end = |s: &str| s.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(s.len());
+
+    for (start, _) in value.match_indices('#') {
+        let boundary = value[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '=' | '(' | ','));
+        let word = &value[start..start + 1 + word_end(&value[start + 1..])];
+        // Skip the likes of "Nord #2"; a 6 character typo is still caught
+        let digits = &word[1..];
+        let looks_like_color = matches!(digits.len(), 3 | 4 | 6 | 8)
+            || (digits.len() >= 3 && digits.chars().all(|c| c.is_ascii_hexdigit()));
+        if boundary && looks_like_color {
+            literals.push(word);
+        }
+    }
+
+    for function in ["rgba(", "rgb("] {
+        for (start, _) in value.match_indices(function) {
+            let inner = &value[start + function.len()..];
+            let word = &inner[..word_end(inner)];
+            // rgb(r, g, b) is a component list rather than hex
+            if inner[word.len()..].starts_with(')') && !word.is_empty() {
+                literals.push(word);
+            }
+        }
+    }
+
+    literals
+}
+
+fn inconsistent_roles(colors: &[RoleColor], issues: &mut Vec<Issue>) {
+    let mut by_role: BTreeMap<Role, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for entry in colors {
+        // Alpha doesn't count: hyprland.conf has it, waybar.css doesn't
+        let [r, g, b, _] = entry.color.to_rgba8();
+        by_role
+            .entry(entry.role)
+            .or_default()
+            .entry(format!("#{:02x}{:02x}{:02x}", r, g, b))
+            .or_default()
+            .push(format!("{}:{}", entry.file, entry.line));
+    }
+
+    for (role, values) in by_role {
+        if values.len() < 2 {
+            continue;
+        }
+        let values: Vec<String> = values
+            .into_iter()
+            .map(|(color, places)| format!("{} ({})", color, places.join(", ")))
+            .collect();
+        issues.push(Issue {
+            severity: Severity::Warning,
+            kind: IssueKind::Inconsistent,
+            file: None,
+            line: None,
+            message: format!("{} differs between files: {}", role, values.join(", ")),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Exporter;
+
+    #[test]
+    fn lints_theme_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let loader = ThemeLoader::with_paths(root.join("themes"), root.join("current/theme"));
+
+        // A freshly exported theme only lacks wallpapers
+        let nord = crate::builtin_theme("nord").unwrap();
+        Exporter::builtin().export(&nord, &loader.theme_dir("nord")).unwrap();
+        let clean = Linter::new().lint(&loader, "nord");
+        assert_eq!(clean.count(Severity::Error), 0, "{:?}", clean.issues);
+        assert!(
+            clean.issues.iter().all(|issue| issue.kind == IssueKind::MissingFile),
+            "{:?}",
+            clean.issues
+        );
+
+        let broken = loader.theme_dir("broken");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(
+            broken.join("waybar.css"),
+            "@define-color foreground #eceff4;\n@define-color background #12345g;\n@define-color background #2e3440;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            broken.join("kitty.conf"),
+            "# kitty\nforeground #eceff4\nbackground #000000\nbackground é12\n",
+        )
+        .unwrap();
+        std::fs::write(broken.join("mako.ini"), "text-color=#eceff4\nbackground-color=#2e3440\n").unwrap();
+
+        let report = Linter::new().lint_all(&loader).unwrap();
+        assert!(report.has_errors());
+        let issues = &report.themes.iter().find(|lint| lint.theme == "broken").unwrap().issues;
+        let find = |kind| issues.iter().filter(move |issue: &&Issue| issue.kind == kind);
+
+        let missing: Vec<_> = find(IssueKind::MissingFile).filter_map(|issue| issue.file.as_deref()).collect();
+        assert_eq!(missing, ["hyprland.conf", "backgrounds/"]);
+        let invalid: Vec<_> = find(IssueKind::InvalidColor).map(|issue| issue.to_string()).collect();
+        assert_eq!(invalid.len(), 2, "{:?}", invalid);
+        assert!(invalid[0].starts_with("kitty.conf:4: "), "{}", invalid[0]);
+        assert!(invalid[1].starts_with("waybar.css:2: "), "{}", invalid[1]);
+        let inconsistent: Vec<_> = find(IssueKind::Inconsistent).map(|issue| issue.message.as_str()).collect();
+        assert_eq!(
+            inconsistent,
+            ["background differs between files: #000000 (kitty.conf:3), #2e3440 (mako.ini:2, waybar.css:3)"]
+        );
+    }
+
+    #[test]
+    fn malformed_terminal_file_keeps_derived_palette() {
+        let root = tempfile::tempdir().unwrap();
+        let loader = ThemeLoader::with_paths(root.path().join("themes"), root.path().join("current/theme"));
+        let nord = crate::builtin_theme("nord").unwrap();
+        Exporter::builtin().export(&nord, &loader.theme_dir("nord")).unwrap();
+        let palette = loader.load_theme("nord").unwrap().terminal;
+        std::fs::write(loader.theme_dir("nord").join("alacritty.toml"), "[colors.normal]\nred = '#bf616a\n").unwrap();
+
+        assert_eq!(loader.load_theme("nord").unwrap().terminal, palette);
+
+        let lint = Linter::new().lint(&loader, "nord");
+        let terminal: Vec<_> = lint
+            .issues
+            .iter()
+            .filter(|issue| issue.file.as_deref() == Some("alacritty.toml"))
+            .collect();
+        assert_eq!(terminal.len(), 1, "{:?}", lint.issues);
+        assert_eq!(terminal[0].severity, Severity::Error);
+    }
+}
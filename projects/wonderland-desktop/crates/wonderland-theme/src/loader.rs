@@ -1,15 +1,32 @@
 //! Theme loading from disk
 
+use crate::audit::WcagLevel;
+use crate::builtin;
 use crate::color::Color;
+use crate::export::Exporter;
+use crate::gtk::GtkExport;
+use crate::hooks::{HookRegistry, SwitchReport};
+use crate::selection::{self, SelectionBackend, SelectionState};
+use crate::terminal::{TerminalColors, TerminalPalette};
+use crate::theme_file::{ThemeFile, ThemeMeta, Variant};
+use crate::tokens::Tokens;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
+/// Links followed from the current link before giving up
+const MAX_LINK_HOPS: usize = 40;
+
 /// A complete theme definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Theme {
     pub name: String,
     pub path: PathBuf,
 
+    // Metadata (from theme.toml)
+    pub meta: ThemeMeta,
+
     // Core colors (from waybar.css)
     pub foreground: Color,
     pub background: Color,
@@ -26,42 +43,214 @@ pub struct Theme {
     pub border: Color,
     pub border_active: Color,
     pub text_muted: Color,
+
+    // ANSI palette (from alacritty.toml, kitty.conf or theme.toml)
+    pub terminal: TerminalPalette,
+
+    // Radii, spacing, typography, borders and shadow (from theme.toml)
+    pub tokens: Tokens,
+
+    // Every @define-color from waybar.css, by name
+    pub named_colors: BTreeMap<String, Color>,
 }
 
 impl Theme {
-    /// Derive extended colors from foreground/background
-    fn derive_extended_colors(fg: Color, bg: Color) -> Self {
-        // Determine if dark or light theme
-        let is_dark = (bg.r + bg.g + bg.b) / 3.0 < 0.5;
+    /// Whether this is a built-in theme that isn't installed on disk
+    pub fn is_builtin(&self) -> bool {
+        self.path.as_os_str().is_empty()
+    }
 
-        let (surface, text_muted) = if is_dark {
-            (bg.lighten(0.08), fg.darken(0.3))
+    /// Derive extended colors from foreground/background
+    pub(crate) fn derive_extended_colors(fg: Color, bg: Color) -> Self {
+        let is_dark = bg.is_dark();
+        // Text roles stay as opaque as the foreground
+        let text_bg = bg.with_alpha(fg.a);
+
+        // Shift surfaces away from the background in perceptual lightness
+        let (surface, border) = if is_dark {
+            (bg.lighten(0.06), bg.lighten(0.18))
         } else {
-            (bg.darken(0.05), fg.lighten(0.3))
+            (bg.darken(0.04), bg.darken(0.14))
         };
 
-        Theme {
+        let mut theme = Theme {
             name: String::new(),
             path: PathBuf::new(),
+            meta: ThemeMeta {
+                variant: if is_dark {
+                    Variant::Dark
+                } else {
+                    Variant::Light
+                },
+                ..Default::default()
+            },
             foreground: fg,
             background: bg,
             primary: fg,  // Use foreground as primary accent
-            secondary: fg.darken(0.2),
+            secondary: fg.mix(&text_bg, 0.2),
             surface,
             error: Color::from_hex("#f38ba8").unwrap_or(fg),
             warning: Color::from_hex("#f9e2af").unwrap_or(fg),
             success: Color::from_hex("#a6e3a1").unwrap_or(fg),
-            border: if is_dark { bg.lighten(0.15) } else { bg.darken(0.15) },
+            border,
             border_active: fg,
-            text_muted,
+            text_muted: fg.mix(&text_bg, 0.3),
+            terminal: TerminalPalette::default(),
+            tokens: Tokens::default(),
+            named_colors: BTreeMap::new(),
+        };
+        theme.terminal = TerminalPalette::derive(&theme);
+        theme
+    }
+
+    /// Blend towards `other` (t = 0.0 gives `self`, 1.0 gives `other`).
+    ///
+    /// Every role and terminal color is mixed in OKLab and every token
+    /// interpolated. Name, path, metadata and font families switch over
+    /// halfway; named colors present in both themes are mixed too.
+    pub fn lerp(&self, other: &Theme, t: f32) -> Theme {
+        if t <= 0.0 {
+            return self.clone();
+        }
+        if t >= 1.0 {
+            return other.clone();
+        }
+
+        let base = if t < 0.5 { self } else { other };
+        let mut theme = Theme {
+            terminal: self.terminal.lerp(&other.terminal, t),
+            tokens: self.tokens.lerp(&other.tokens, t),
+            named_colors: base
+                .named_colors
+                .iter()
+                .map(|(name, color)| {
+                    let mixed = match (self.named(name), other.named(name)) {
+                        (Some(from), Some(to)) => from.mix(&to, t),
+                        _ => *color,
+                    };
+                    (name.clone(), mixed)
+                })
+                .collect(),
+            ..base.clone()
+        };
+        for role in Role::ALL {
+            *theme.color_mut(role) = self.color(role).mix(&other.color(role), t);
+        }
+        theme
+    }
+
+    /// Look up a color defined in the theme's waybar.css
+    pub fn named(&self, name: &str) -> Option<Color> {
+        self.named_colors.get(name).copied()
+    }
+
+    /// Get the color assigned to a role
+    pub fn color(&self, role: Role) -> Color {
+        match role {
+            Role::Foreground => self.foreground,
+            Role::Background => self.background,
+            Role::Primary => self.primary,
+            Role::Secondary => self.secondary,
+            Role::Surface => self.surface,
+            Role::Error => self.error,
+            Role::Warning => self.warning,
+            Role::Success => self.success,
+            Role::Border => self.border,
+            Role::BorderActive => self.border_active,
+            Role::TextMuted => self.text_muted,
         }
     }
+
+    /// Mutable access to the color assigned to a role
+    pub fn color_mut(&mut self, role: Role) -> &mut Color {
+        match role {
+            Role::Foreground => &mut self.foreground,
+            Role::Background => &mut self.background,
+            Role::Primary => &mut self.primary,
+            Role::Secondary => &mut self.secondary,
+            Role::Surface => &mut self.surface,
+            Role::Error => &mut self.error,
+            Role::Warning => &mut self.warning,
+            Role::Success => &mut self.success,
+            Role::Border => &mut self.border,
+            Role::BorderActive => &mut self.border_active,
+            Role::TextMuted => &mut self.text_muted,
+        }
+    }
+}
+
+/// A semantic color slot of a [`Theme`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Foreground,
+    Background,
+    Primary,
+    Secondary,
+    Surface,
+    Error,
+    Warning,
+    Success,
+    Border,
+    BorderActive,
+    TextMuted,
+}
+
+impl Role {
+    pub const ALL: [Role; 11] = [
+        Role::Foreground,
+        Role::Background,
+        Role::Primary,
+        Role::Secondary,
+        Role::Surface,
+        Role::Error,
+        Role::Warning,
+        Role::Success,
+        Role::Border,
+        Role::BorderActive,
+        Role::TextMuted,
+    ];
+
+    /// Key used in theme.toml and templates
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Foreground => "foreground",
+            Role::Background => "background",
+            Role::Primary => "primary",
+            Role::Secondary => "secondary",
+            Role::Surface => "surface",
+            Role::Error => "error",
+            Role::Warning => "warning",
+            Role::Success => "success",
+            Role::Border => "border",
+            Role::BorderActive => "border_active",
+            Role::TextMuted => "text_muted",
+        }
+    }
+
+    /// Look up a role by its theme.toml key
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.name() == name)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// Theme loader with directory scanning and hot-reload support
+#[derive(Debug, Clone)]
 pub struct ThemeLoader {
     themes_dir: PathBuf,
     current_link: PathBuf,
+    state_file: PathBuf,
+    store_dir: PathBuf,
+    contrast_correction: Option<WcagLevel>,
+    variant: Option<Variant>,
+    hooks: HookRegistry,
+    gtk: Option<GtkExport>,
 }
 
 impl ThemeLoader {
@@ -70,10 +259,10 @@ impl ThemeLoader {
         let home = std::env::var("HOME").map_err(|_| ThemeError::NoHomeDir)?;
         let home = PathBuf::from(home);
 
-        Ok(Self {
-            themes_dir: home.join(crate::THEME_DIR),
-            current_link: home.join(crate::CURRENT_THEME),
-        })
+        Ok(Self::with_paths(
+            home.join(crate::THEME_DIR),
+            home.join(crate::CURRENT_THEME),
+        ))
     }
 
     /// Create with custom paths
@@ -81,9 +270,160 @@ impl ThemeLoader {
         Self {
             themes_dir,
             current_link,
+            state_file: wonderland_config::state_dir().join(crate::SELECTION_STATE),
+            store_dir: selection::store_dir(),
+            contrast_correction: None,
+            variant: None,
+            hooks: HookRegistry::default(),
+            gtk: None,
         }
     }
 
+    /// Where to record the selection when the current link is store-managed
+    pub fn with_state_file(mut self, state_file: PathBuf) -> Self {
+        self.state_file = state_file;
+        self
+    }
+
+    /// Use a nix store other than `/nix/store` / `$NIX_STORE_DIR`
+    pub fn with_store_dir(mut self, store_dir: PathBuf) -> Self {
+        self.store_dir = store_dir;
+        self
+    }
+
+    /// Nudge derived colors until every pairing meets `level`.
+    ///
+    /// Colors set explicitly in waybar.css or theme.toml are never changed.
+    pub fn with_contrast_correction(mut self, level: WcagLevel) -> Self {
+        self.contrast_correction = Some(level);
+        self
+    }
+
+    /// Prefer this variant of themes that declare `[variants.*]`. Without
+    /// it, themes load as their theme.toml describes them.
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+
+    /// Hooks to run after [`ThemeLoader::set_current`] swaps the theme
+    pub fn with_hooks(mut self, hooks: HookRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Also write GTK's gtk.css colors when switching themes, before the
+    /// reload hooks run
+    pub fn with_gtk(mut self, gtk: GtkExport) -> Self {
+        self.gtk = Some(gtk);
+        self
+    }
+
+    /// Path of the `current/theme` symlink
+    pub fn current_link(&self) -> &Path {
+        &self.current_link
+    }
+
+    /// Path of the selection state file used by [`SelectionBackend::StateFile`]
+    pub fn state_file(&self) -> &Path {
+        &self.state_file
+    }
+
+    /// How the active theme is recorded: the symlink itself, or the state
+    /// file when home-manager points the link (or its directory) into the
+    /// read-only nix store
+    pub fn selection_backend(&self) -> SelectionBackend {
+        let target_in_store = self
+            .link_target()
+            .is_ok_and(|target| target.starts_with(&self.store_dir));
+        let dir_in_store = self
+            .current_link
+            .parent()
+            .and_then(|dir| dir.canonicalize().ok())
+            .is_some_and(|dir| dir.starts_with(&self.store_dir));
+
+        if target_in_store || dir_in_store {
+            SelectionBackend::StateFile
+        } else {
+            SelectionBackend::Symlink
+        }
+    }
+
+    /// Where the current link finally points, made absolute. home-manager
+    /// links it to a link in its files derivation, which points at the
+    /// theme, so the whole chain is followed.
+    fn link_target(&self) -> Result<PathBuf, ThemeError> {
+        let mut link = self.current_link.clone();
+        for _ in 0..MAX_LINK_HOPS {
+            let target = std::fs::read_link(&link).map_err(|e| ThemeError::Io(link.clone(), e))?;
+            let target = match link.parent() {
+                Some(dir) if target.is_relative() => dir.join(target),
+                _ => target,
+            };
+            if !target.symlink_metadata().is_ok_and(|meta| meta.file_type().is_symlink()) {
+                return Ok(target);
+            }
+            link = target;
+        }
+
+        Err(ThemeError::Io(
+            self.current_link.clone(),
+            std::io::Error::other("too many levels of symbolic links"),
+        ))
+    }
+
+    /// Name and directory of the active theme
+    fn current_selection(&self) -> Result<(String, PathBuf), ThemeError> {
+        let target = self.link_target()?;
+
+        if self.selection_backend() == SelectionBackend::StateFile {
+            match SelectionState::load(&self.state_file) {
+                Some(state) if state.link_target == target => {
+                    let dir = self
+                        .theme_path(&state.theme)
+                        .ok_or_else(|| ThemeError::NotFound(state.theme.clone()))?;
+                    return Ok((state.theme, dir));
+                }
+                Some(_) => tracing::info!("Theme link was repointed, ignoring saved selection"),
+                None => {}
+            }
+        }
+
+        let name = selection::theme_name_from_path(&target, &self.store_dir)
+            .ok_or_else(|| ThemeError::InvalidThemePath(target.clone()))?;
+        let dir = self.themes_dir.join(&name);
+        Ok((name, if dir.exists() { dir } else { target }))
+    }
+
+    /// Directory of a theme: from the themes dir, or the link target when
+    /// it's the (possibly store-only) theme the link points at
+    fn theme_path(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.themes_dir.join(name);
+        if dir.exists() {
+            return Some(dir);
+        }
+
+        self.link_target().ok().filter(|target| {
+            target.exists()
+                && selection::theme_name_from_path(target, &self.store_dir).as_deref() == Some(name)
+        })
+    }
+
+    /// theme.toml content and path of the theme `child` extends: an
+    /// installed theme, or a built-in one. A theme extending its own name
+    /// gets the built-in it shadows.
+    fn theme_source(&self, child: &str, name: &str) -> Result<Option<(String, PathBuf)>, ThemeError> {
+        if let Some(dir) = self.theme_path(name).filter(|_| child != name) {
+            let path = dir.join("theme.toml");
+            if path.exists() {
+                let content =
+                    std::fs::read_to_string(&path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+                return Ok(Some((content, path)));
+            }
+        }
+        Ok(builtin::source(name))
+    }
+
     /// List all available themes
     pub fn list_themes(&self) -> Result<Vec<String>, ThemeError> {
         let mut themes = Vec::new();
@@ -106,54 +446,203 @@ impl ThemeLoader {
 
     /// Get the currently active theme name
     pub fn current_theme_name(&self) -> Result<String, ThemeError> {
-        let target = std::fs::read_link(&self.current_link)
-            .map_err(|e| ThemeError::Io(self.current_link.clone(), e))?;
+        self.current_selection().map(|(name, _)| name)
+    }
 
-        // Extract theme name from path
-        target
-            .file_name()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_string())
-            .ok_or(ThemeError::InvalidThemePath(target))
+    /// Get the directory of the currently active theme
+    pub fn current_theme_dir(&self) -> Result<PathBuf, ThemeError> {
+        self.current_selection().map(|(_, dir)| dir)
+    }
+
+    /// Make `name` the active theme, then run the reload hooks.
+    ///
+    /// The theme is loaded first so a broken theme is never activated, and
+    /// the symlink (or, for a store-managed link, the state file) is
+    /// replaced with a rename so readers always see either the old or the
+    /// new theme. Hook failures don't undo the switch; they are listed in
+    /// the report.
+    ///
+    /// A built-in theme that isn't installed yet is exported into the themes
+    /// dir first.
+    pub fn set_current(&self, name: &str) -> Result<SwitchReport, ThemeError> {
+        validate_name(name)?;
+        let mut theme = self.load_theme(name)?;
+        // The link needs a directory to point at
+        if theme.is_builtin() {
+            let dest = self.theme_dir(name);
+            Exporter::new()?.export(&theme, &dest)?;
+            theme.path = dest;
+        }
+        let previous = self.current_theme_name().ok();
+
+        match self.selection_backend() {
+            SelectionBackend::Symlink => self.swap_link(&theme.path)?,
+            SelectionBackend::StateFile => SelectionState {
+                theme: name.to_string(),
+                link_target: self.link_target()?,
+            }
+            .save(&self.state_file)?,
+        }
+        tracing::info!("switched theme to {}", name);
+
+        let mut hooks: Vec<_> = self.gtk.iter().map(|gtk| gtk.write_step(&theme)).collect();
+        hooks.extend(self.hooks.run(name, &theme.path));
+
+        Ok(SwitchReport {
+            theme: name.to_string(),
+            previous,
+            hooks,
+        })
+    }
+
+    /// Point the current link at `target` via a temporary link and rename
+    fn swap_link(&self, target: &Path) -> Result<(), ThemeError> {
+        let link = &self.current_link;
+        let parent = link
+            .parent()
+            .ok_or_else(|| ThemeError::InvalidThemePath(link.clone()))?;
+        std::fs::create_dir_all(parent).map_err(|e| ThemeError::Io(parent.to_path_buf(), e))?;
+
+        let file_name = link.file_name().and_then(|n| n.to_str()).unwrap_or("theme");
+        let tmp = parent.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+        // Leftover from a crashed switch
+        let _ = std::fs::remove_file(&tmp);
+
+        std::os::unix::fs::symlink(target, &tmp).map_err(|e| ThemeError::Io(tmp.clone(), e))?;
+        std::fs::rename(&tmp, link).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            ThemeError::Io(link.clone(), e)
+        })
     }
 
     /// Load the current theme
     pub fn load_current(&self) -> Result<Theme, ThemeError> {
-        let name = self.current_theme_name()?;
-        self.load_theme(&name)
+        let (name, theme_path) = self.current_selection()?;
+        self.load_theme_at(&name, theme_path, self.variant)
     }
 
-    /// Load a theme by name
+    /// Load the current theme, falling back to the default built-in theme
+    /// when there is none or it can't be loaded. Apps should use this.
+    pub fn load_current_or_builtin(&self) -> Theme {
+        self.load_current().unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to load current theme ({}), using built-in {}",
+                e,
+                builtin::DEFAULT_BUILTIN
+            );
+            builtin::default_theme()
+        })
+    }
+
+    /// Load a theme by name. Installed themes shadow built-ins of the same
+    /// name.
     pub fn load_theme(&self, name: &str) -> Result<Theme, ThemeError> {
-        let theme_path = self.themes_dir.join(name);
+        self.load_theme_variant(name, self.variant)
+    }
 
-        if !theme_path.exists() {
-            return Err(ThemeError::NotFound(name.to_string()));
+    /// Load a theme by name with a `[variants.*]` table applied; `None`
+    /// loads it as declared
+    pub fn load_theme_variant(&self, name: &str, variant: Option<Variant>) -> Result<Theme, ThemeError> {
+        match self.theme_path(name) {
+            Some(theme_path) => self.load_theme_at(name, theme_path, variant),
+            None => builtin::load(name, variant).ok_or_else(|| ThemeError::NotFound(name.to_string())),
         }
+    }
+
+    fn load_theme_at(
+        &self,
+        name: &str,
+        theme_path: PathBuf,
+        variant: Option<Variant>,
+    ) -> Result<Theme, ThemeError> {
+        // Extended colors and metadata from theme.toml, if present
+        let theme_toml = theme_path.join("theme.toml");
+        let theme_file = if theme_toml.exists() {
+            let content = std::fs::read_to_string(&theme_toml)
+                .map_err(|e| ThemeError::Io(theme_toml.clone(), e))?;
+            Some(ThemeFile::resolve(name, &content, &theme_toml, variant, &|child: &str, parent: &str| {
+                self.theme_source(child, parent)
+            })?)
+        } else {
+            None
+        };
 
         // Parse waybar.css for core colors
         let waybar_css = theme_path.join("waybar.css");
-        let (foreground, background) = if waybar_css.exists() {
+        let named_colors = if waybar_css.exists() {
             parse_waybar_css(&waybar_css)?
         } else {
-            // Default fallback
+            BTreeMap::new()
+        };
+
+        let (mut foreground, mut background) = if waybar_css.exists() {
             (
-                Color::from_hex("#cdd6f4").unwrap(),
-                Color::from_hex("#1e1e2e").unwrap(),
+                named_colors
+                    .get("foreground")
+                    .copied()
+                    .ok_or(ThemeError::MissingColor("foreground".to_string()))?,
+                named_colors
+                    .get("background")
+                    .copied()
+                    .ok_or(ThemeError::MissingColor("background".to_string()))?,
             )
+        } else {
+            let fallback = builtin::default_theme();
+            (fallback.foreground, fallback.background)
         };
 
+        if let Some(file) = &theme_file {
+            let (fg, bg) = file.base_colors(&theme_toml)?;
+            foreground = fg.unwrap_or(foreground);
+            background = bg.unwrap_or(background);
+        }
+
         // Build theme with derived colors
         let mut theme = Theme::derive_extended_colors(foreground, background);
         theme.name = name.to_string();
         theme.path = theme_path;
+        theme.meta.display_name = name.to_string();
+        theme.named_colors = named_colors;
+
+        // Let theme.toml override derived colors
+        let mut locked = vec![Role::Foreground, Role::Background];
+        if let Some(file) = &theme_file {
+            file.apply(&mut theme, &theme_toml)?;
+            locked.extend(file.colors.roles());
+        }
 
-        // TODO: Load extended colors from theme.toml if it exists
-        // This allows themes to override derived colors
+        if let Some(level) = self.contrast_correction {
+            for fix in theme.correct_contrast(level, &locked) {
+                tracing::info!(
+                    "{}: adjusted {} from {} to {} for contrast",
+                    name,
+                    fix.role,
+                    fix.from,
+                    fix.to
+                );
+            }
+        }
+
+        // The palette follows the final roles unless the theme ships one
+        theme.terminal = TerminalPalette::derive(&theme);
+        // A malformed terminal file is left for the linter to report
+        match TerminalColors::from_theme_dir(&theme.path) {
+            Ok(Some(colors)) => colors.apply(&mut theme.terminal),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("{}: keeping the derived terminal palette: {}", name, e),
+        }
+        if let Some(file) = &theme_file {
+            file.apply_terminal(&mut theme.terminal, &theme_toml)?;
+        }
 
         Ok(theme)
     }
 
+    /// Get path to a theme's directory
+    pub fn theme_dir(&self, theme_name: &str) -> PathBuf {
+        self.themes_dir.join(theme_name)
+    }
+
     /// Get path to theme backgrounds directory
     pub fn backgrounds_dir(&self, theme_name: &str) -> PathBuf {
         self.themes_dir.join(theme_name).join("backgrounds")
@@ -166,45 +655,32 @@ impl Default for ThemeLoader {
     }
 }
 
-/// Parse waybar.css for foreground and background colors
-fn parse_waybar_css(path: &Path) -> Result<(Color, Color), ThemeError> {
-    let content = std::fs::read_to_string(path)
-        .map_err(|e| ThemeError::Io(path.to_path_buf(), e))?;
-
-    let mut foreground = None;
-    let mut background = None;
-
-    for line in content.lines() {
-        let line = line.trim();
-
-        if line.starts_with("@define-color foreground") {
-            if let Some(color) = extract_color(line) {
-                foreground = Some(Color::from_hex(&color)?);
-            }
-        } else if line.starts_with("@define-color background") {
-            if let Some(color) = extract_color(line) {
-                background = Some(Color::from_hex(&color)?);
-            }
-        }
-    }
-
-    Ok((
-        foreground.ok_or(ThemeError::MissingColor("foreground".to_string()))?,
-        background.ok_or(ThemeError::MissingColor("background".to_string()))?,
-    ))
+/// Names that are a single plain directory name
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
 }
 
-/// Extract color value from CSS @define-color line
-fn extract_color(line: &str) -> Option<String> {
-    // Format: @define-color name #hexcolor;
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() >= 3 {
-        Some(parts[2].trim_end_matches(';').to_string())
+/// Check a theme name before it becomes a directory under the themes dir
+pub(crate) fn validate_name(name: &str) -> Result<(), ThemeError> {
+    if is_valid_name(name) {
+        Ok(())
     } else {
-        None
+        Err(ThemeError::InvalidName(name.to_string()))
     }
 }
 
+/// Parse all @define-color declarations from waybar.css
+fn parse_waybar_css(path: &Path) -> Result<BTreeMap<String, Color>, ThemeError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ThemeError::Io(path.to_path_buf(), e))?;
+
+    crate::css::parse_define_colors(&content).map_err(|e| ThemeError::Css(path.to_path_buf(), e))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ThemeError {
     #[error("HOME environment variable not set")]
@@ -216,12 +692,188 @@ pub enum ThemeError {
     #[error("Invalid theme path: {0}")]
     InvalidThemePath(PathBuf),
 
+    #[error("Invalid theme name {0:?}: use ASCII letters, digits, `-`, `_` and `.`")]
+    InvalidName(String),
+
     #[error("Missing required color: {0}")]
     MissingColor(String),
 
+    #[error("Theme inheritance cycle: {}", .0.join(" -> "))]
+    InheritanceCycle(Vec<String>),
+
+    #[error("Theme {0} extends unknown theme {1}")]
+    UnknownParent(String, String),
+
     #[error("IO error at {0}: {1}")]
     Io(PathBuf, std::io::Error),
 
+    #[error("Invalid theme file {0}: {1}")]
+    Toml(PathBuf, toml::de::Error),
+
+    #[error("Invalid color for `{key}` in {path}: {source}")]
+    InvalidColor {
+        path: PathBuf,
+        key: String,
+        source: crate::color::ColorError,
+    },
+
+    #[error("Failed to write {0}: {1}")]
+    Serialize(PathBuf, toml::ser::Error),
+
+    #[error("Template error in {0}: {1}")]
+    Template(String, crate::export::TemplateError),
+
+    #[error("Cannot import {0}: {1}")]
+    Import(PathBuf, String),
+
+    #[error("Theme already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Cannot read image {0}: {1}")]
+    Image(PathBuf, image::ImageError),
+
+    #[error("Invalid terminal colors in {0}: {1}")]
+    Terminal(PathBuf, String),
+
+    #[error("Invalid theme package {0}: {1}")]
+    Package(PathBuf, String),
+
+    #[error("Cannot render preview: {0}")]
+    Preview(String),
+
+    #[error("Invalid CSS in {0}: {1}")]
+    Css(PathBuf, crate::css::CssError),
+
+    #[error("Config error: {0}")]
+    Config(Box<wonderland_config::ConfigError>),
+
+    #[error("File watcher error: {0}")]
+    Watch(notify::Error),
+
     #[error("Color parse error: {0}")]
     ColorParse(#[from] crate::color::ColorError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_current_swaps_the_link() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let themes = root.join("themes");
+        for name in ["nord", "gruvbox"] {
+            std::fs::create_dir_all(themes.join(name)).unwrap();
+        }
+        let loader = ThemeLoader::with_paths(themes.clone(), root.join("current/theme"))
+            .with_hooks(HookRegistry::default().with_hook(crate::ReloadHook::new("fail", "exit 1")));
+
+        let first = loader.set_current("nord").unwrap();
+        assert_eq!(first.previous, None);
+        assert!(!first.succeeded());
+
+        let second = loader.set_current("gruvbox").unwrap();
+        assert_eq!(second.previous.as_deref(), Some("nord"));
+        assert_eq!(loader.current_theme_name().unwrap(), "gruvbox");
+
+        assert!(matches!(loader.set_current("missing"), Err(ThemeError::NotFound(_))));
+        assert!(matches!(loader.set_current("../.."), Err(ThemeError::InvalidName(_))));
+        assert_eq!(loader.current_theme_name().unwrap(), "gruvbox");
+    }
+
+    #[test]
+    fn falls_back_to_builtins() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let loader = ThemeLoader::with_paths(root.join("themes"), root.join("current/theme"));
+
+        // Nothing on disk at all
+        assert!(loader.load_current().is_err());
+        assert_eq!(loader.load_current_or_builtin().name, crate::DEFAULT_BUILTIN);
+        assert!(loader.load_theme("nord").unwrap().is_builtin());
+
+        // Activating a built-in installs it
+        loader.set_current("nord").unwrap();
+        let current = loader.load_current().unwrap();
+        assert_eq!(current.name, "nord");
+        assert_eq!(current.path, root.join("themes/nord"));
+        assert_eq!(current.primary.to_hex(), "#88c0d0");
+
+        // Installed themes can extend built-in ones, variants included
+        let mine = root.join("themes/mine");
+        std::fs::create_dir_all(&mine).unwrap();
+        std::fs::write(mine.join("theme.toml"), "extends = \"gruvbox\"\n[colors]\nprimary = \"#fe8019\"\n").unwrap();
+        let dark = loader.load_theme("mine").unwrap();
+        assert_eq!((dark.primary.to_hex(), dark.background.to_hex()), ("#fe8019".into(), "#282828".into()));
+        let light = loader.load_theme_variant("mine", Some(Variant::Light)).unwrap();
+        assert_eq!(light.background.to_hex(), "#fbf1c7");
+        assert_eq!(light.meta.variant, Variant::Light);
+
+        // Shadowing a built-in and extending it
+        std::fs::write(
+            root.join("themes/nord/theme.toml"),
+            "extends = \"nord\"\n[colors]\nprimary = \"#bf616a\"\n",
+        )
+        .unwrap();
+        let nord = loader.load_theme("nord").unwrap();
+        assert_eq!((nord.primary.to_hex(), nord.background.to_hex()), ("#bf616a".into(), "#2e3440".into()));
+    }
+
+    #[test]
+    fn store_managed_link_uses_state_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let store = root.join("store");
+        let declared = store.join("0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-tokyo-night");
+        let themes = root.join("themes");
+        std::fs::create_dir_all(&declared).unwrap();
+        std::fs::create_dir_all(themes.join("nord")).unwrap();
+        std::fs::create_dir_all(root.join("current")).unwrap();
+        std::os::unix::fs::symlink(&declared, root.join("current/theme")).unwrap();
+
+        let loader = ThemeLoader::with_paths(themes, root.join("current/theme"))
+            .with_store_dir(store.clone())
+            .with_state_file(root.join("state/current-theme.toml"));
+        assert_eq!(loader.selection_backend(), SelectionBackend::StateFile);
+        assert_eq!(loader.current_theme_name().unwrap(), "tokyo-night");
+        assert_eq!(loader.current_theme_dir().unwrap(), declared);
+
+        loader.set_current("nord").unwrap();
+        assert_eq!(std::fs::read_link(root.join("current/theme")).unwrap(), declared);
+        assert_eq!(loader.current_theme_name().unwrap(), "nord");
+
+        // Back to the declared theme, which only exists in the store
+        loader.set_current("tokyo-night").unwrap();
+        assert_eq!(loader.load_current().unwrap().path, declared);
+
+        // home-manager repointing the link overrides the saved selection
+        loader.set_current("nord").unwrap();
+        let redeclared = store.join("1a2b3c4d5f6g7h8i9j0k1l2m3n4p5q6r-gruvbox");
+        std::fs::create_dir_all(&redeclared).unwrap();
+        std::fs::remove_file(root.join("current/theme")).unwrap();
+        std::os::unix::fs::symlink(&redeclared, root.join("current/theme")).unwrap();
+        assert_eq!(loader.current_theme_name().unwrap(), "gruvbox");
+    }
+
+    #[test]
+    fn follows_home_manager_link_chain() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let store = root.join("store");
+        let declared = store.join("0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-tokyo-night");
+        let files = store.join("1a2b3c4d5f6g7h8i9j0k1l2m3n4p5q6r-home-manager-files/.config/theme/current/theme");
+        std::fs::create_dir_all(&declared).unwrap();
+        std::fs::create_dir_all(files.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(root.join("current")).unwrap();
+        std::os::unix::fs::symlink(&declared, &files).unwrap();
+        std::os::unix::fs::symlink(&files, root.join("current/theme")).unwrap();
+
+        let loader = ThemeLoader::with_paths(root.join("themes"), root.join("current/theme"))
+            .with_store_dir(store)
+            .with_state_file(root.join("state/current-theme.toml"));
+        assert_eq!(loader.selection_backend(), SelectionBackend::StateFile);
+        assert_eq!(loader.current_theme_name().unwrap(), "tokyo-night");
+        assert_eq!(loader.current_theme_dir().unwrap(), declared);
+    }
+}
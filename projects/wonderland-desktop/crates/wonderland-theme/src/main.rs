@@ -0,0 +1,390 @@
+//! wonderland-theme command line tool
+//!
+//! Built with the `cli` feature.
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use wonderland_theme::{
+    import, package, preview, Exporter, Generator, GtkExport, HookRegistry, LintReport, Linter,
+    Package, Preview, Schedule, Scheduler, SchemeFormat, Severity, ThemeError, ThemeLoader,
+    Variant, WcagLevel,
+};
+
+#[derive(Parser)]
+#[command(name = "wonderland-theme", about = "Manage Wonderland themes")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render every app config of a theme into a theme directory, or pack
+    /// the theme into a .wltheme package
+    Export {
+        /// Theme to export
+        theme: String,
+        /// Output directory or package (defaults to ./<theme> or
+        /// ./<theme>.wltheme)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Directory with templates overriding the built-in ones
+        #[arg(long, conflicts_with = "package")]
+        templates: Option<PathBuf>,
+        /// Write a .wltheme package of the theme as it is installed
+        #[arg(long)]
+        package: bool,
+        /// Version recorded in the package manifest
+        #[arg(long, default_value = "1.0.0", requires = "package")]
+        version: String,
+    },
+    /// Install a .wltheme package, or create a theme from a base16/base24,
+    /// Alacritty, kitty or iTerm2 scheme
+    Import {
+        /// Package or scheme file to import
+        file: PathBuf,
+        /// Theme name (defaults to the package's or scheme's name)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Scheme format (detected from the extension by default)
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+        /// Replace an existing theme with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Create a theme from the colors of a wallpaper
+    Generate {
+        /// PNG or JPEG wallpaper
+        wallpaper: PathBuf,
+        /// Theme name (defaults to the wallpaper's file name)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Force a dark or light theme instead of following the wallpaper
+        #[arg(long, value_enum)]
+        variant: Option<VariantArg>,
+        /// Replace an existing theme with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Switch the active theme and run the reload hooks
+    Set {
+        /// Theme to activate
+        theme: String,
+        /// Only swap the theme, without running any hooks
+        #[arg(long)]
+        no_hooks: bool,
+        /// Leave GTK's gtk.css files alone
+        #[arg(long)]
+        no_gtk: bool,
+    },
+    /// Write the current theme's colors into GTK's gtk.css files
+    Gtk {
+        /// Put back the gtk.css files from before Wonderland wrote them
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Switch between the light and dark theme of theme-schedule.toml
+    Schedule {
+        /// Apply the schedule once and print the next switch instead of
+        /// running until killed
+        #[arg(long)]
+        once: bool,
+    },
+    /// Render a PNG preview of a theme
+    Preview {
+        /// Theme to preview
+        theme: String,
+        /// Write the PNG here instead of the preview cache
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        #[arg(long, default_value_t = 320)]
+        width: u32,
+        #[arg(long, default_value_t = 200)]
+        height: u32,
+        /// Draw on a plain background instead of the theme's wallpaper
+        #[arg(long)]
+        no_wallpaper: bool,
+        /// Preview cache (defaults to ~/.cache/wonderland/theme-previews)
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+    },
+    /// Check installed themes for missing files, bad colors and poor contrast
+    Lint {
+        /// Themes to check (defaults to every installed theme)
+        themes: Vec<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        /// Contrast level the palette has to reach
+        #[arg(long, value_enum, default_value = "aa")]
+        level: Level,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Level {
+    AaLarge,
+    Aa,
+    Aaa,
+}
+
+impl From<Level> for WcagLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::AaLarge => WcagLevel::AaLarge,
+            Level::Aa => WcagLevel::Aa,
+            Level::Aaa => WcagLevel::Aaa,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Base16,
+    Alacritty,
+    Kitty,
+    Iterm2,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum VariantArg {
+    Dark,
+    Light,
+}
+
+impl From<VariantArg> for Variant {
+    fn from(variant: VariantArg) -> Self {
+        match variant {
+            VariantArg::Dark => Variant::Dark,
+            VariantArg::Light => Variant::Light,
+        }
+    }
+}
+
+impl From<Format> for SchemeFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Base16 => SchemeFormat::Base16,
+            Format::Alacritty => SchemeFormat::Alacritty,
+            Format::Kitty => SchemeFormat::Kitty,
+            Format::Iterm2 => SchemeFormat::Iterm2,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    // Logs go to stderr, so they don't mix with paths and JSON on stdout
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, ThemeError> {
+    match cli.command {
+        Command::Export {
+            theme,
+            out,
+            package: true,
+            version,
+            ..
+        } => {
+            let loader = ThemeLoader::new()?;
+            let out = out.unwrap_or_else(|| PathBuf::from(format!("{}.{}", theme, package::EXTENSION)));
+            Package::from_theme(&loader, &theme, &version)?.write(&out)?;
+            println!("{}", out.display());
+        }
+        Command::Export {
+            theme,
+            out,
+            templates,
+            ..
+        } => {
+            let loader = ThemeLoader::new()?;
+            let theme_data = loader.load_theme(&theme)?;
+
+            let mut exporter = Exporter::new()?;
+            if let Some(dir) = templates {
+                exporter = exporter.with_template_dir(&dir)?;
+            }
+
+            let out = out.unwrap_or_else(|| PathBuf::from(&theme));
+            for path in exporter.export(&theme_data, &out)? {
+                println!("{}", path.display());
+            }
+        }
+        Command::Import {
+            file,
+            name,
+            format,
+            force,
+        } => {
+            let loader = ThemeLoader::new()?;
+            if file.extension().is_some_and(|ext| ext == package::EXTENSION) {
+                let dir = Package::read(&file)?.install(&loader, name.as_deref(), force)?;
+                println!("{}", dir.display());
+            } else {
+                let name = import::import(&loader, &file, format.map(Into::into), name.as_deref(), force)?;
+                println!("{}", loader.theme_dir(&name).display());
+            }
+        }
+        Command::Generate {
+            wallpaper,
+            name,
+            variant,
+            force,
+        } => {
+            let name = name.unwrap_or_else(|| {
+                let stem = wallpaper.file_stem().and_then(|s| s.to_str()).unwrap_or("wallpaper");
+                import::slugify(stem)
+            });
+
+            let mut generator = Generator::new();
+            if let Some(variant) = variant {
+                generator = generator.with_variant(variant.into());
+            }
+
+            let loader = ThemeLoader::new()?;
+            let theme = generator.write(&loader, &wallpaper, &name, force)?;
+            println!("{}", theme.path.display());
+        }
+        Command::Set {
+            theme,
+            no_hooks,
+            no_gtk,
+        } => {
+            let hooks = if no_hooks {
+                HookRegistry::default()
+            } else {
+                HookRegistry::load()?
+            };
+            let mut loader = ThemeLoader::new()?.with_hooks(hooks);
+            if !no_gtk {
+                loader = loader.with_gtk(GtkExport::new());
+            }
+            let report = loader.set_current(&theme)?;
+
+            println!("{}", report.theme);
+            for hook in &report.hooks {
+                let ms = hook.duration.as_millis();
+                if hook.status.is_ok() {
+                    println!("  ok   {} ({}ms)", hook.name, ms);
+                } else {
+                    println!("  FAIL {} ({}ms): {}", hook.name, ms, hook.status);
+                }
+            }
+
+            if !report.succeeded() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Gtk { restore } => {
+            let gtk = GtkExport::new();
+            let paths = if restore {
+                gtk.restore()?
+            } else {
+                gtk.write(&ThemeLoader::new()?.load_current()?)?
+            };
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
+        Command::Schedule { once } => {
+            let loader = ThemeLoader::new()?
+                .with_hooks(HookRegistry::load()?)
+                .with_gtk(GtkExport::new());
+            let scheduler = Scheduler::new(loader, Schedule::load()?);
+            if !once {
+                scheduler.run();
+            }
+
+            if let Some(report) = scheduler.apply(&chrono::Local::now())? {
+                println!("{}", report.theme);
+            }
+            match scheduler.next_transition() {
+                Some(next) => println!(
+                    "next: {} at {}",
+                    scheduler.schedule().theme(next.variant),
+                    next.at.format("%Y-%m-%d %H:%M")
+                ),
+                None => println!("no switch within half a year"),
+            }
+        }
+        Command::Preview {
+            theme,
+            out,
+            width,
+            height,
+            no_wallpaper,
+            cache_dir,
+        } => {
+            let theme = ThemeLoader::new()?.load_theme(&theme)?;
+            let preview = Preview::new()
+                .with_size(width, height)
+                .with_wallpaper(!no_wallpaper);
+
+            let path = match out {
+                Some(out) => {
+                    let png = preview.render(&theme)?;
+                    std::fs::write(&out, png).map_err(|e| ThemeError::Io(out.clone(), e))?;
+                    out
+                }
+                None => preview.cached(&theme, &cache_dir.unwrap_or_else(preview::cache_dir))?,
+            };
+            println!("{}", path.display());
+        }
+        Command::Lint { themes, json, level } => {
+            let loader = ThemeLoader::new()?;
+            let linter = Linter::new().with_level(level.into());
+            let report = if themes.is_empty() {
+                linter.lint_all(&loader)?
+            } else {
+                LintReport {
+                    themes: themes.iter().map(|name| linter.lint(&loader, name)).collect(),
+                }
+            };
+
+            if json {
+                let json = serde_json::to_string_pretty(&report).expect("lint report serializes");
+                println!("{}", json);
+            } else {
+                for theme in &report.themes {
+                    if theme.issues.is_empty() {
+                        println!("{}: ok", theme.theme);
+                        continue;
+                    }
+                    println!("{}:", theme.theme);
+                    for issue in &theme.issues {
+                        let severity = match issue.severity {
+                            Severity::Error => "error",
+                            Severity::Warning => "warning",
+                        };
+                        println!("  {:<7} {}", severity, issue);
+                    }
+                }
+                println!(
+                    "{} themes, {} errors, {} warnings",
+                    report.themes.len(),
+                    report.count(Severity::Error),
+                    report.count(Severity::Warning)
+                );
+            }
+
+            if report.has_errors() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
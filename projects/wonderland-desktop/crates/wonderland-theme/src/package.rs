@@ -0,0 +1,425 @@
+//! Portable theme packages
+//!
+//! A `.wltheme` file is a zstd-compressed tar archive of a theme directory:
+//!
+//! ```text
+//! manifest.toml      name, version, author, preview and checksums
+//! preview.png        rendered with [`crate::Preview`]
+//! theme/...          the theme directory, backgrounds included
+//! ```
+//!
+//! The manifest lists the SHA-256 of every other file in the archive.
+//! [`Package::read`] refuses archives with anything else in them: entries
+//! outside these paths, `..` components, links, or files that don't match
+//! their checksum.
+
+use crate::export::Exporter;
+use crate::loader::{is_valid_name, validate_name, ThemeError, ThemeLoader};
+use crate::preview::Preview;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// File extension of theme packages
+pub const EXTENSION: &str = "wltheme";
+
+/// Archive layout version, bumped on incompatible changes
+const FORMAT: u32 = 1;
+
+const MANIFEST: &str = "manifest.toml";
+const PREVIEW: &str = "preview.png";
+const THEME_DIR: &str = "theme";
+
+/// Upper bound for the unpacked size; themes are config files and a few
+/// wallpapers
+const MAX_UNPACKED_SIZE: u64 = 512 * 1024 * 1024;
+
+/// manifest.toml of a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    /// Theme directory name
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Archive path of the preview image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// Hex SHA-256 of every file but the manifest, by archive path
+    pub checksums: BTreeMap<String, String>,
+}
+
+/// A theme package, read into memory
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub manifest: Manifest,
+    /// Theme directory contents, by path relative to the directory
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    preview: Option<Vec<u8>>,
+}
+
+impl Package {
+    /// Package a theme: the installed directory, or a built-in theme
+    /// rendered with the exporter's templates
+    pub fn from_theme(loader: &ThemeLoader, name: &str, version: &str) -> Result<Self, ThemeError> {
+        let theme = loader.load_theme(name)?;
+        let files = if theme.is_builtin() {
+            let staging = std::env::temp_dir().join(format!("wonderland-package-{}-{}", name, std::process::id()));
+            let files = Exporter::new()?
+                .export(&theme, &staging)
+                .and_then(|_| read_dir(&staging));
+            let _ = std::fs::remove_dir_all(&staging);
+            files?
+        } else {
+            read_dir(&theme.path)?
+        };
+        let preview = Preview::new().render(&theme)?;
+
+        let mut checksums = BTreeMap::new();
+        for (path, data) in &files {
+            checksums.insert(archive_path(path), sha256(data));
+        }
+        checksums.insert(PREVIEW.to_string(), sha256(&preview));
+
+        Ok(Self {
+            manifest: Manifest {
+                format: FORMAT,
+                name: name.to_string(),
+                version: version.to_string(),
+                author: theme.meta.author.clone(),
+                preview: Some(PREVIEW.to_string()),
+                checksums,
+            },
+            files,
+            preview: Some(preview),
+        })
+    }
+
+    /// Paths of the theme's files, relative to its directory
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// PNG preview, if the package has one
+    pub fn preview(&self) -> Option<&[u8]> {
+        self.preview.as_deref()
+    }
+
+    /// Write the package to `out`
+    pub fn write(&self, out: &Path) -> Result<(), ThemeError> {
+        let io_err = |e| ThemeError::Io(out.to_path_buf(), e);
+        let manifest = toml::to_string_pretty(&self.manifest)
+            .map_err(|e| ThemeError::Serialize(out.to_path_buf(), e))?;
+
+        let file = std::fs::File::create(out).map_err(io_err)?;
+        let encoder = zstd::Encoder::new(file, 0).map_err(io_err)?;
+        let mut tar = tar::Builder::new(encoder);
+
+        append(&mut tar, MANIFEST, manifest.as_bytes()).map_err(io_err)?;
+        if let Some(preview) = &self.preview {
+            append(&mut tar, PREVIEW, preview).map_err(io_err)?;
+        }
+        for (path, data) in &self.files {
+            append(&mut tar, &archive_path(path), data).map_err(io_err)?;
+        }
+
+        tar.into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(io_err)?;
+        Ok(())
+    }
+
+    /// Read and validate a package
+    pub fn read(path: &Path) -> Result<Self, ThemeError> {
+        let invalid = |message: String| ThemeError::Package(path.to_path_buf(), message);
+        let io_err = |e| ThemeError::Io(path.to_path_buf(), e);
+
+        let file = std::fs::File::open(path).map_err(io_err)?;
+        let decoder = zstd::Decoder::new(file).map_err(io_err)?;
+        let mut archive = tar::Archive::new(decoder);
+
+        let mut entries = BTreeMap::new();
+        let mut unpacked = 0;
+        for entry in archive.entries().map_err(io_err)? {
+            let entry = entry.map_err(io_err)?;
+            let name = entry_path(&entry).map_err(invalid)?;
+
+            match entry.header().entry_type() {
+                tar::EntryType::Directory => continue,
+                tar::EntryType::Regular | tar::EntryType::Continuous => {}
+                other => return Err(invalid(format!("{} is a {:?} entry, only files are allowed", name, other))),
+            }
+
+            unpacked += entry.size();
+            if unpacked > MAX_UNPACKED_SIZE {
+                return Err(invalid(format!("unpacks to more than {} MiB", MAX_UNPACKED_SIZE >> 20)));
+            }
+
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry.take(MAX_UNPACKED_SIZE).read_to_end(&mut data).map_err(io_err)?;
+            if entries.insert(name.clone(), data).is_some() {
+                return Err(invalid(format!("{} appears twice", name)));
+            }
+        }
+
+        let manifest = entries
+            .remove(MANIFEST)
+            .ok_or_else(|| invalid(format!("no {}", MANIFEST)))?;
+        let manifest = std::str::from_utf8(&manifest)
+            .map_err(|_| invalid(format!("{} is not UTF-8", MANIFEST)))?;
+        let manifest: Manifest = toml::from_str(manifest).map_err(|e| ThemeError::Toml(path.join(MANIFEST), e))?;
+        validate_manifest(&manifest).map_err(invalid)?;
+
+        // Exactly the files the manifest lists, with matching contents
+        for (name, data) in &entries {
+            match manifest.checksums.get(name) {
+                Some(expected) if expected.eq_ignore_ascii_case(&sha256(data)) => {}
+                Some(_) => return Err(invalid(format!("checksum mismatch for {}", name))),
+                None => return Err(invalid(format!("{} is not listed in the manifest", name))),
+            }
+        }
+        if let Some(missing) = manifest.checksums.keys().find(|name| !entries.contains_key(*name)) {
+            return Err(invalid(format!("{} is listed in the manifest but missing", missing)));
+        }
+
+        let preview = match &manifest.preview {
+            Some(name) => Some(
+                entries
+                    .remove(name)
+                    .ok_or_else(|| invalid(format!("preview {} is missing", name)))?,
+            ),
+            None => None,
+        };
+
+        let mut files = BTreeMap::new();
+        for (name, data) in entries {
+            match name.strip_prefix(&format!("{}/", THEME_DIR)) {
+                Some(relative) => {
+                    files.insert(PathBuf::from(relative), data);
+                }
+                None => return Err(invalid(format!("unexpected file {}", name))),
+            }
+        }
+        if !files.contains_key(Path::new("theme.toml")) && !files.contains_key(Path::new("waybar.css")) {
+            return Err(invalid("the theme has neither a theme.toml nor a waybar.css".to_string()));
+        }
+
+        Ok(Self {
+            manifest,
+            files,
+            preview,
+        })
+    }
+
+    /// Install the theme into the loader's themes dir, as `name` or the
+    /// manifest's name. Returns the theme directory.
+    pub fn install(&self, loader: &ThemeLoader, name: Option<&str>, overwrite: bool) -> Result<PathBuf, ThemeError> {
+        let name = name.unwrap_or(&self.manifest.name);
+        validate_name(name)?;
+
+        let dest = loader.theme_dir(name);
+        if dest.exists() && !overwrite {
+            return Err(ThemeError::AlreadyExists(name.to_string()));
+        }
+
+        // Unpack next to the destination, then swap it in, so a failed
+        // import never leaves half a theme behind
+        let staging = dest.with_file_name(format!(".{}.{}-{}", name, EXTENSION, std::process::id()));
+        let result = self.unpack(&staging).and_then(|_| {
+            if dest.exists() {
+                std::fs::remove_dir_all(&dest).map_err(|e| ThemeError::Io(dest.clone(), e))?;
+            }
+            std::fs::rename(&staging, &dest).map_err(|e| ThemeError::Io(dest.clone(), e))
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_dir_all(&staging);
+        }
+        result.map(|_| dest)
+    }
+
+    fn unpack(&self, dir: &Path) -> Result<(), ThemeError> {
+        for (relative, data) in &self.files {
+            let path = dir.join(relative);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| ThemeError::Io(parent.to_path_buf(), e))?;
+            }
+            std::fs::write(&path, data).map_err(|e| ThemeError::Io(path.clone(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Every file below `dir`, by relative path. Symlinked files are followed,
+/// symlinked directories skipped.
+fn read_dir(dir: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, ThemeError> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative) = pending.pop() {
+        let current = dir.join(&relative);
+        let entries = std::fs::read_dir(&current).map_err(|e| ThemeError::Io(current.clone(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| ThemeError::Io(current.clone(), e))?;
+            let path = entry.path();
+            let file_type = entry.file_type().map_err(|e| ThemeError::Io(path.clone(), e))?;
+
+            if file_type.is_dir() {
+                pending.push(relative.join(entry.file_name()));
+            } else if path.is_file() {
+                let data = std::fs::read(&path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+                files.insert(relative.join(entry.file_name()), data);
+            } else {
+                tracing::warn!("Not packaging {}: not a regular file", path.display());
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Archive path of a file in the theme directory
+fn archive_path(relative: &Path) -> String {
+    let mut path = THEME_DIR.to_string();
+    for component in relative.components() {
+        path.push('/');
+        path.push_str(&component.as_os_str().to_string_lossy());
+    }
+    path
+}
+
+/// An entry's path, normalized to `/`-separated plain components
+fn entry_path<R: Read>(entry: &tar::Entry<R>) -> Result<String, String> {
+    let path = entry.path().map_err(|e| e.to_string())?;
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => match part.to_str() {
+                Some(part) => parts.push(part),
+                None => return Err(format!("{} is not a UTF-8 path", path.display())),
+            },
+            Component::CurDir => {}
+            _ => return Err(format!("{} points outside the package", path.display())),
+        }
+    }
+
+    if parts.is_empty() {
+        return Err("empty entry path".to_string());
+    }
+    Ok(parts.join("/"))
+}
+
+fn validate_manifest(manifest: &Manifest) -> Result<(), String> {
+    if manifest.format > FORMAT {
+        return Err(format!(
+            "package format {} is newer than the supported {}",
+            manifest.format, FORMAT
+        ));
+    }
+    if !is_valid_name(&manifest.name) {
+        return Err(format!("invalid theme name `{}`", manifest.name));
+    }
+    if manifest.version.trim().is_empty() {
+        return Err("empty version".to_string());
+    }
+    if manifest.preview.as_deref().is_some_and(|preview| preview != PREVIEW) {
+        return Err(format!("the preview has to be {}", PREVIEW));
+    }
+    Ok(())
+}
+
+fn append<W: std::io::Write>(tar: &mut tar::Builder<W>, path: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    // Same theme, same package
+    header.set_mtime(0);
+    tar.append_data(&mut header, path, data)
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packages_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let loader = ThemeLoader::with_paths(root.join("themes"), root.join("current/theme"));
+        let out = root.join("nord.wltheme");
+
+        let package = Package::from_theme(&loader, "nord", "1.2.0").unwrap();
+        package.write(&out).unwrap();
+
+        let read = Package::read(&out).unwrap();
+        assert_eq!(read.manifest, package.manifest);
+        assert!(read.files().any(|file| file == Path::new("waybar.css")));
+        assert!(read.preview().is_some_and(|png| png.starts_with(b"\x89PNG")));
+
+        let dir = read.install(&loader, Some("shared"), false).unwrap();
+        assert_eq!(loader.load_theme("shared").unwrap().primary.to_hex(), "#88c0d0");
+        assert!(matches!(read.install(&loader, Some("shared"), false), Err(ThemeError::AlreadyExists(_))));
+        assert_eq!(read.install(&loader, Some("shared"), true).unwrap(), dir);
+        assert!(read.install(&loader, Some("../escape"), true).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_packages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+
+        let write = |name: &str, entries: &[(&str, &[u8])]| {
+            let path = root.join(name);
+            let encoder = zstd::Encoder::new(std::fs::File::create(&path).unwrap(), 0).unwrap();
+            let mut tar = tar::Builder::new(encoder);
+            for (entry, data) in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                // append_data refuses `..`, so write the raw name
+                header.as_gnu_mut().unwrap().name[..entry.len()].copy_from_slice(entry.as_bytes());
+                header.set_cksum();
+                tar.append(&header, *data).unwrap();
+            }
+            tar.into_inner().unwrap().finish().unwrap();
+            path
+        };
+        let css: &[u8] = b"@define-color foreground #eceff4;\n@define-color background #2e3440;\n";
+        let manifest = |extra: &str| {
+            format!(
+                "format = 1\nname = \"mine\"\nversion = \"1\"\n[checksums]\n\"theme/waybar.css\" = \"{}\"\n{}",
+                sha256(css),
+                extra
+            )
+        };
+
+        let good = manifest("");
+        let path = write("good.wltheme", &[("manifest.toml", good.as_bytes()), ("theme/waybar.css", css)]);
+        assert!(Package::read(&path).is_ok());
+
+        let traversal = manifest("\"../evil\" = \"00\"\n");
+        let tampered: &[u8] = b"@define-color foreground #000;";
+        let cases = [
+            ("traversal", vec![("manifest.toml", traversal.as_bytes()), ("theme/waybar.css", css), ("../evil", b"00".as_slice())]),
+            ("unlisted", vec![("manifest.toml", good.as_bytes()), ("theme/waybar.css", css), ("theme/extra.css", css)]),
+            ("tampered", vec![("manifest.toml", good.as_bytes()), ("theme/waybar.css", tampered)]),
+        ];
+        for (name, entries) in cases {
+            let path = write(&format!("{}.wltheme", name), &entries);
+            assert!(matches!(Package::read(&path), Err(ThemeError::Package(..))), "{}", name);
+        }
+
+        let bad_name = good.replace("\"mine\"", "\"../mine\"");
+        let path = write("name.wltheme", &[("manifest.toml", bad_name.as_bytes()), ("theme/waybar.css", css)]);
+        assert!(matches!(Package::read(&path), Err(ThemeError::Package(..))));
+    }
+}
@@ -0,0 +1,348 @@
+//! Theme preview thumbnails
+//!
+//! Draws a mock window in a theme's colors and tokens: title bar, text,
+//! a primary and a secondary button, and the terminal palette, optionally
+//! over the theme's first wallpaper from its backgrounds dir. Rendering is
+//! done on the CPU with tiny-skia, so previews can be generated headless,
+//! e.g. during a Nix build.
+//!
+//! Previews are cached as `<hash>.png`, where the hash covers everything
+//! that affects the picture: the theme's colors and tokens, the wallpaper
+//! file's contents and the preview size.
+
+use crate::color::Color;
+use crate::loader::{Theme, ThemeError};
+use image::imageops::FilterType;
+use std::path::{Path, PathBuf};
+use tiny_skia::{FillRule, IntSize, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform};
+
+/// Size of the design space the mock window is laid out in; the drawing is
+/// scaled to the requested size
+const DESIGN_WIDTH: f32 = 320.0;
+const DESIGN_HEIGHT: f32 = 200.0;
+
+/// Bump whenever the drawing changes, so cached previews are redrawn
+const RENDER_VERSION: u32 = 1;
+
+/// Renders theme previews
+#[derive(Debug, Clone, Copy)]
+pub struct Preview {
+    width: u32,
+    height: u32,
+    wallpaper: bool,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Preview {
+    /// 320x200 over the theme's wallpaper
+    pub fn new() -> Self {
+        Self {
+            width: DESIGN_WIDTH as u32,
+            height: DESIGN_HEIGHT as u32,
+            wallpaper: true,
+        }
+    }
+
+    /// Output size in pixels
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Draw over the theme's wallpaper, or a plain background
+    pub fn with_wallpaper(mut self, wallpaper: bool) -> Self {
+        self.wallpaper = wallpaper;
+        self
+    }
+
+    /// Render the preview as PNG
+    pub fn render(&self, theme: &Theme) -> Result<Vec<u8>, ThemeError> {
+        let mut pixmap = Pixmap::new(self.width, self.height)
+            .ok_or_else(|| ThemeError::Preview(format!("invalid size {}x{}", self.width, self.height)))?;
+        pixmap.fill(skia_color(theme.background.lighten(0.04)));
+
+        if let Some(path) = self.wallpaper_path(theme) {
+            match image::open(&path) {
+                Ok(image) => self.draw_wallpaper(&mut pixmap, &image),
+                Err(e) => tracing::warn!("Skipping preview wallpaper {}: {}", path.display(), e),
+            }
+        }
+
+        // Uniform scale, centered, so rounded corners stay round
+        let scale = (self.width as f32 / DESIGN_WIDTH).min(self.height as f32 / DESIGN_HEIGHT);
+        let transform = Transform::from_translate(
+            (self.width as f32 - DESIGN_WIDTH * scale) / 2.0,
+            (self.height as f32 - DESIGN_HEIGHT * scale) / 2.0,
+        )
+        .pre_scale(scale, scale);
+        draw_window(&mut Canvas { pixmap: &mut pixmap, transform }, theme);
+
+        pixmap
+            .encode_png()
+            .map_err(|e| ThemeError::Preview(e.to_string()))
+    }
+
+    /// Hash of everything the preview depends on, as 16 hex digits
+    pub fn content_hash(&self, theme: &Theme) -> Result<String, ThemeError> {
+        // Where a theme lives and what it's called don't change its looks
+        let content = Theme {
+            name: String::new(),
+            path: PathBuf::new(),
+            ..theme.clone()
+        };
+        let json = serde_json::to_vec(&content).map_err(|e| ThemeError::Preview(e.to_string()))?;
+
+        let mut hash = Fnv1a::new();
+        hash.write(&RENDER_VERSION.to_le_bytes());
+        hash.write(&self.width.to_le_bytes());
+        hash.write(&self.height.to_le_bytes());
+        hash.write(&json);
+        if let Some(path) = self.wallpaper_path(theme) {
+            let bytes = std::fs::read(&path).map_err(|e| ThemeError::Io(path.clone(), e))?;
+            hash.write(&bytes);
+        }
+
+        Ok(format!("{:016x}", hash.finish()))
+    }
+
+    /// Path of the cached preview in `cache_dir`, rendering it first if
+    /// it isn't there yet
+    pub fn cached(&self, theme: &Theme, cache_dir: &Path) -> Result<PathBuf, ThemeError> {
+        let path = cache_dir.join(format!("{}.png", self.content_hash(theme)?));
+        if path.exists() {
+            return Ok(path);
+        }
+
+        let png = self.render(theme)?;
+        std::fs::create_dir_all(cache_dir).map_err(|e| ThemeError::Io(cache_dir.to_path_buf(), e))?;
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, png).map_err(|e| ThemeError::Io(tmp.clone(), e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            ThemeError::Io(path.clone(), e)
+        })?;
+
+        Ok(path)
+    }
+
+    /// First image in the theme's backgrounds dir, the one the wallpaper
+    /// hook shows
+    fn wallpaper_path(&self, theme: &Theme) -> Option<PathBuf> {
+        if !self.wallpaper || theme.is_builtin() {
+            return None;
+        }
+
+        let mut images: Vec<PathBuf> = std::fs::read_dir(theme.path.join("backgrounds"))
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+            })
+            .collect();
+        images.sort();
+        images.into_iter().next()
+    }
+
+    /// Cover the whole pixmap with the wallpaper, cropping the excess
+    fn draw_wallpaper(&self, pixmap: &mut Pixmap, image: &image::DynamicImage) {
+        let mut data = image
+            .resize_to_fill(self.width, self.height, FilterType::Triangle)
+            .to_rgba8()
+            .into_raw();
+        // tiny-skia wants premultiplied alpha
+        for pixel in data.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u16;
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u16 * alpha + 127) / 255) as u8;
+            }
+        }
+
+        let wallpaper = IntSize::from_wh(self.width, self.height).and_then(|size| Pixmap::from_vec(data, size));
+        if let Some(wallpaper) = wallpaper {
+            pixmap.draw_pixmap(0, 0, wallpaper.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+        }
+    }
+}
+
+/// Default location of cached previews
+pub fn cache_dir() -> PathBuf {
+    wonderland_config::cache_dir().join("theme-previews")
+}
+
+/// A pixmap with the design-space transform applied to every shape
+struct Canvas<'a> {
+    pixmap: &'a mut Pixmap,
+    transform: Transform,
+}
+
+impl Canvas<'_> {
+    fn fill(&mut self, (x, y, w, h): (f32, f32, f32, f32), radius: f32, color: Color) {
+        if let Some(path) = rounded_rect(x, y, w, h, radius) {
+            let paint = paint(color);
+            self.pixmap
+                .fill_path(&path, &paint, FillRule::Winding, self.transform, None);
+        }
+    }
+
+    fn stroke(&mut self, (x, y, w, h): (f32, f32, f32, f32), radius: f32, width: f32, color: Color) {
+        // Keep the stroke inside the shape, like iced borders
+        let inset = width / 2.0;
+        if let Some(path) = rounded_rect(x + inset, y + inset, w - width, h - width, (radius - inset).max(0.0)) {
+            let stroke = Stroke {
+                width,
+                ..Stroke::default()
+            };
+            self.pixmap
+                .stroke_path(&path, &paint(color), &stroke, self.transform, None);
+        }
+    }
+
+    fn circle(&mut self, x: f32, y: f32, radius: f32, color: Color) {
+        if let Some(path) = PathBuilder::from_circle(x, y, radius) {
+            self.pixmap
+                .fill_path(&path, &paint(color), FillRule::Winding, self.transform, None);
+        }
+    }
+}
+
+/// The mock window, in design-space coordinates
+fn draw_window(canvas: &mut Canvas, theme: &Theme) {
+    let tokens = &theme.tokens;
+    let window = (24.0, 18.0, 272.0, 164.0);
+    let title_height = 22.0;
+    let radius = tokens.radius.large;
+
+    // Window with a surface-colored title bar
+    canvas.fill(window, radius, theme.background);
+    canvas.fill((window.0, window.1, window.2, title_height + radius), radius, theme.surface);
+    canvas.fill((window.0, window.1 + title_height, window.2, radius), 0.0, theme.background);
+    for (i, color) in [theme.error, theme.warning, theme.success].into_iter().enumerate() {
+        canvas.circle(38.0 + 12.0 * i as f32, window.1 + title_height / 2.0, 4.0, color);
+    }
+
+    // Heading, body text and muted text, as bars
+    canvas.fill((40.0, 52.0, 120.0, 7.0), 3.5, theme.foreground);
+    canvas.fill((40.0, 65.0, 196.0, 5.0), 2.5, theme.foreground.with_alpha(theme.foreground.a * 0.8));
+    canvas.fill((40.0, 76.0, 160.0, 5.0), 2.5, theme.text_muted);
+    canvas.fill((40.0, 86.0, 112.0, 5.0), 2.5, theme.text_muted);
+
+    // Primary button, outlined secondary button and an accent dot
+    let primary = (40.0, 102.0, 72.0, 22.0);
+    canvas.fill(primary, tokens.radius.medium, theme.primary);
+    canvas.fill((54.0, 110.5, 44.0, 5.0), 2.5, theme.background);
+    let secondary = (120.0, 102.0, 72.0, 22.0);
+    canvas.stroke(secondary, tokens.radius.medium, tokens.border.thin.max(1.0), theme.border_active);
+    canvas.fill((134.0, 110.5, 44.0, 5.0), 2.5, theme.foreground);
+    canvas.circle(214.0, 113.0, 8.0, theme.secondary);
+
+    // Terminal palette: normal colors above their bright variants
+    for (i, color) in theme.terminal.colors.iter().enumerate() {
+        let (column, row) = ((i % 8) as f32, (i / 8) as f32);
+        canvas.fill(
+            (40.0 + column * 30.0, 138.0 + row * 16.0, 28.0, 14.0),
+            tokens.radius.small.min(7.0),
+            *color,
+        );
+    }
+
+    canvas.stroke(window, radius, tokens.border.thin.max(1.0), theme.border);
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    let [r, g, b, a] = color.to_rgba8();
+    tiny_skia::Color::from_rgba8(r, g, b, a)
+}
+
+fn rounded_rect(x: f32, y: f32, w: f32, h: f32, radius: f32) -> Option<tiny_skia::Path> {
+    let radius = radius.min(w / 2.0).min(h / 2.0).max(0.0);
+    if radius == 0.0 {
+        return Rect::from_xywh(x, y, w, h).map(PathBuilder::from_rect);
+    }
+
+    let (right, bottom) = (x + w, y + h);
+    let mut path = PathBuilder::new();
+    path.move_to(x + radius, y);
+    path.line_to(right - radius, y);
+    path.quad_to(right, y, right, y + radius);
+    path.line_to(right, bottom - radius);
+    path.quad_to(right, bottom, right - radius, bottom);
+    path.line_to(x + radius, bottom);
+    path.quad_to(x, bottom, x, bottom - radius);
+    path.line_to(x, y + radius);
+    path.quad_to(x, y, x + radius, y);
+    path.close();
+    path.finish()
+}
+
+/// 64-bit FNV-1a; unlike std's hasher, stable across Rust versions, so
+/// previews generated at build time are found at runtime
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::builtin_theme;
+
+    #[test]
+    fn renders_and_caches_previews() {
+        let theme = builtin_theme("nord").unwrap();
+        let png = Preview::new().with_size(640, 400).render(&theme).unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (640, 400));
+        // Inside the window, below the text: the theme background
+        let pixel = image.get_pixel(500, 200);
+        assert_eq!(&pixel.0, &theme.background.to_rgba8());
+        // First terminal swatch
+        let pixel = image.get_pixel(2 * 50, 2 * 142);
+        assert_eq!(&pixel.0, &theme.terminal.colors[0].to_rgba8());
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let preview = Preview::new();
+        let first = preview.cached(&theme, dir).unwrap();
+        assert_eq!(preview.cached(&theme, dir).unwrap(), first);
+
+        let mut changed = theme.clone();
+        changed.primary = Color::from_hex("#ff0000").unwrap();
+        assert_ne!(preview.cached(&changed, dir).unwrap(), first);
+        // Renaming doesn't change the picture
+        let renamed = Theme {
+            name: "other".to_string(),
+            ..theme
+        };
+        assert_eq!(preview.cached(&renamed, dir).unwrap(), first);
+    }
+}
@@ -0,0 +1,391 @@
+//! Automatic light/dark switching
+//!
+//! Declared in `~/.config/wonderland/theme-schedule.toml`:
+//!
+//! ```toml
+//! light = "catppuccin-latte"
+//! dark = "catppuccin"
+//!
+//! # Switch at fixed local times...
+//! [times]
+//! light = "07:00"
+//! dark = "19:30"
+//!
+//! # ...or at sunrise and sunset
+//! [location]
+//! latitude = 52.52
+//! longitude = 13.40
+//! ```
+//!
+//! Sunrise and sunset are computed offline with the NOAA sunrise equation,
+//! accurate to a minute or two. Past the polar circles the sun may not
+//! rise or set for days; the light theme is kept through polar day and the
+//! dark one through polar night.
+
+use crate::hooks::SwitchReport;
+use crate::loader::{ThemeError, ThemeLoader};
+use crate::theme_file::Variant;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Config file name, relative to the wonderland config dir
+const CONFIG_NAME: &str = "theme-schedule";
+
+/// How far around a date transitions are searched for; polar night lasts
+/// up to half a year at the poles
+const SEARCH_DAYS: i64 = 190;
+
+/// Longest the scheduler sleeps at once, so suspend and clock changes are
+/// noticed
+const MAX_SLEEP: Duration = Duration::from_secs(15 * 60);
+
+/// Which theme to use when
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "ScheduleFile")]
+pub struct Schedule {
+    /// Theme for daytime
+    pub light: String,
+    /// Theme for nighttime
+    pub dark: String,
+    pub trigger: Trigger,
+}
+
+/// When the light and dark periods start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    /// Fixed local times of day
+    Fixed { light: NaiveTime, dark: NaiveTime },
+    /// Sunrise and sunset at a location, in degrees (north and east positive)
+    Sun { latitude: f64, longitude: f64 },
+}
+
+/// A switch from one variant to the other
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition<Tz: TimeZone> {
+    pub at: DateTime<Tz>,
+    /// Variant that starts at `at`
+    pub variant: Variant,
+}
+
+/// Sun events of one day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Daylight {
+    Normal {
+        sunrise: DateTime<Utc>,
+        sunset: DateTime<Utc>,
+    },
+    /// The sun stays above the horizon all day
+    PolarDay,
+    /// The sun stays below the horizon all day
+    PolarNight,
+}
+
+/// theme-schedule.toml as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScheduleFile {
+    light: String,
+    dark: String,
+    times: Option<TimesSection>,
+    location: Option<LocationSection>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimesSection {
+    light: String,
+    dark: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LocationSection {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl TryFrom<ScheduleFile> for Schedule {
+    type Error = String;
+
+    fn try_from(file: ScheduleFile) -> Result<Self, Self::Error> {
+        let time = |key: &str, value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| format!("times.{} must be HH:MM, got {:?}", key, value))
+        };
+
+        let trigger = match (file.times, file.location) {
+            (Some(times), None) => {
+                let (light, dark) = (time("light", &times.light)?, time("dark", &times.dark)?);
+                if light == dark {
+                    return Err("times.light and times.dark must differ".to_string());
+                }
+                Trigger::Fixed { light, dark }
+            }
+            (None, Some(location)) => {
+                if !(-90.0..=90.0).contains(&location.latitude) {
+                    return Err(format!("latitude {} is out of range", location.latitude));
+                }
+                if !(-180.0..=180.0).contains(&location.longitude) {
+                    return Err(format!("longitude {} is out of range", location.longitude));
+                }
+                Trigger::Sun {
+                    latitude: location.latitude,
+                    longitude: location.longitude,
+                }
+            }
+            (Some(_), Some(_)) => return Err("set either [times] or [location], not both".to_string()),
+            (None, None) => return Err("missing [times] or [location]".to_string()),
+        };
+
+        Ok(Schedule {
+            light: file.light,
+            dark: file.dark,
+            trigger,
+        })
+    }
+}
+
+impl Schedule {
+    /// Load `theme-schedule.toml`
+    pub fn load() -> Result<Self, ThemeError> {
+        wonderland_config::load(CONFIG_NAME).map_err(|e| ThemeError::Config(Box::new(e)))
+    }
+
+    /// Theme configured for a variant
+    pub fn theme(&self, variant: Variant) -> &str {
+        match variant {
+            Variant::Light => &self.light,
+            Variant::Dark => &self.dark,
+        }
+    }
+
+    /// Variant that should be active at `at`
+    pub fn variant_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Variant {
+        let date = at.date_naive();
+        self.transitions(&at.timezone(), date - chrono::Days::new(SEARCH_DAYS as u64), 2 * SEARCH_DAYS)
+            .into_iter()
+            .take_while(|transition| transition.at <= *at)
+            .last()
+            .map(|transition| transition.variant)
+            .unwrap_or_else(|| match self.trigger {
+                Trigger::Sun { latitude, longitude } => match sun_times(date, latitude, longitude) {
+                    Daylight::PolarNight => Variant::Dark,
+                    _ => Variant::Light,
+                },
+                Trigger::Fixed { .. } => Variant::Dark,
+            })
+    }
+
+    /// The next switch after `at`, if there is one within half a year
+    pub fn next_transition<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<Transition<Tz>> {
+        let current = self.variant_at(at);
+        self.transitions(&at.timezone(), at.date_naive() - chrono::Days::new(1), SEARCH_DAYS)
+            .into_iter()
+            .find(|transition| transition.at > *at && transition.variant != current)
+    }
+
+    /// Every period start on `days` days from `from`, in order
+    fn transitions<Tz: TimeZone>(&self, tz: &Tz, from: NaiveDate, days: i64) -> Vec<Transition<Tz>> {
+        let mut transitions = Vec::new();
+        for date in from.iter_days().take(days as usize) {
+            match self.trigger {
+                Trigger::Fixed { light, dark } => {
+                    for (time, variant) in [(light, Variant::Light), (dark, Variant::Dark)] {
+                        // Times skipped by a DST change happen an hour later
+                        let local = date.and_time(time);
+                        let at = tz
+                            .from_local_datetime(&local)
+                            .earliest()
+                            .or_else(|| tz.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest());
+                        if let Some(at) = at {
+                            transitions.push(Transition { at, variant });
+                        }
+                    }
+                }
+                Trigger::Sun { latitude, longitude } => {
+                    if let Daylight::Normal { sunrise, sunset } = sun_times(date, latitude, longitude) {
+                        transitions.push(Transition {
+                            at: sunrise.with_timezone(tz),
+                            variant: Variant::Light,
+                        });
+                        transitions.push(Transition {
+                            at: sunset.with_timezone(tz),
+                            variant: Variant::Dark,
+                        });
+                    }
+                }
+            }
+        }
+
+        transitions.sort_by(|a, b| a.at.cmp(&b.at));
+        transitions
+    }
+}
+
+/// Sunrise and sunset on `date` (UTC) at a location, in degrees with north
+/// and east positive
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Daylight {
+    // Days since 2000-01-01 12:00 UTC (J2000)
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date");
+    let n = date.signed_duration_since(j2000).num_days() as f64 + 0.0008;
+
+    // Mean solar time, solar mean anomaly and the equation of the center
+    let mean_time = n - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_time).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * anomaly.sin() + 0.0200 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+
+    // Solar noon as a Julian date, and the sun's declination
+    let transit = 2451545.0 + mean_time + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * 23.4397f64.to_radians().sin()).asin();
+
+    // Hour angle at which the sun's upper edge touches the horizon,
+    // including atmospheric refraction
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = ((-0.833f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if cos_hour_angle < -1.0 {
+        return Daylight::PolarDay;
+    }
+    if cos_hour_angle > 1.0 {
+        return Daylight::PolarNight;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let to_utc = |julian: f64| {
+        let seconds = ((julian - 2440587.5) * 86400.0).round() as i64;
+        DateTime::from_timestamp(seconds, 0).expect("timestamp in range")
+    };
+    Daylight::Normal {
+        sunrise: to_utc(transit - hour_angle / 360.0),
+        sunset: to_utc(transit + hour_angle / 360.0),
+    }
+}
+
+/// Switches the current theme according to a [`Schedule`]
+pub struct Scheduler {
+    loader: ThemeLoader,
+    schedule: Schedule,
+}
+
+impl Scheduler {
+    pub fn new(loader: ThemeLoader, schedule: Schedule) -> Self {
+        Self { loader, schedule }
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// The next switch from now
+    pub fn next_transition(&self) -> Option<Transition<Local>> {
+        self.schedule.next_transition(&Local::now())
+    }
+
+    /// Activate the theme scheduled for `at`, unless it's already current
+    pub fn apply<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Result<Option<SwitchReport>, ThemeError> {
+        let theme = self.schedule.theme(self.schedule.variant_at(at));
+        if self.loader.current_theme_name().ok().as_deref() == Some(theme) {
+            return Ok(None);
+        }
+        self.loader.set_current(theme).map(Some)
+    }
+
+    /// Apply the schedule now and then at every transition, forever.
+    ///
+    /// Between transitions the current theme is left alone, so switching
+    /// by hand sticks until the next sunrise or sunset.
+    pub fn run(&self) -> ! {
+        let mut active = None;
+        loop {
+            let now = Local::now();
+            let variant = self.schedule.variant_at(&now);
+            if active != Some(variant) {
+                match self.apply(&now) {
+                    Ok(Some(report)) => tracing::info!("scheduled switch to {}", report.theme),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("scheduled switch failed: {}", e),
+                }
+                active = Some(variant);
+            }
+
+            let sleep = self
+                .next_transition()
+                .and_then(|next| (next.at - Local::now()).to_std().ok())
+                .map_or(MAX_SLEEP, |until| until.min(MAX_SLEEP));
+            // Land just after the transition, not just before it
+            std::thread::sleep(sleep + Duration::from_secs(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn computes_sunrise_and_sunset() {
+        // Berlin, summer solstice: sunrise 02:43 UTC, sunset 19:33 UTC
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let Daylight::Normal { sunrise, sunset } = sun_times(date, 52.52, 13.40) else {
+            panic!("Berlin has sunrise in June");
+        };
+        let minutes = |t: DateTime<Utc>| (t.hour() * 60 + t.minute()) as i32;
+        assert!((minutes(sunrise) - (2 * 60 + 43)).abs() <= 3, "sunrise {}", sunrise);
+        assert!((minutes(sunset) - (19 * 60 + 33)).abs() <= 3, "sunset {}", sunset);
+
+        // Tromsø has midnight sun in June and polar night in December
+        assert_eq!(sun_times(date, 69.65, 18.96), Daylight::PolarDay);
+        let december = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        assert_eq!(sun_times(december, 69.65, 18.96), Daylight::PolarNight);
+    }
+
+    #[test]
+    fn finds_variant_and_next_transition() {
+        let schedule: Schedule = toml::from_str(
+            "light = \"day\"\ndark = \"night\"\n[times]\nlight = \"07:00\"\ndark = \"19:30\"\n",
+        )
+        .unwrap();
+
+        let morning = utc(2024, 3, 1, 9, 0);
+        assert_eq!(schedule.variant_at(&morning), Variant::Light);
+        assert_eq!(schedule.theme(schedule.variant_at(&utc(2024, 3, 1, 23, 0))), "night");
+        assert_eq!(schedule.variant_at(&utc(2024, 3, 1, 3, 0)), Variant::Dark);
+
+        let next = schedule.next_transition(&morning).unwrap();
+        assert_eq!((next.at, next.variant), (utc(2024, 3, 1, 19, 30), Variant::Dark));
+        let next = schedule.next_transition(&next.at).unwrap();
+        assert_eq!((next.at, next.variant), (utc(2024, 3, 2, 7, 0), Variant::Light));
+
+        // Polar night: dark all day, light again once the sun is back
+        let tromso: Schedule = toml::from_str(
+            "light = \"day\"\ndark = \"night\"\n[location]\nlatitude = 69.65\nlongitude = 18.96\n",
+        )
+        .unwrap();
+        let midwinter = utc(2024, 12, 21, 12, 0);
+        assert_eq!(tromso.variant_at(&midwinter), Variant::Dark);
+        let sunrise = tromso.next_transition(&midwinter).unwrap();
+        assert_eq!(sunrise.variant, Variant::Light);
+        assert_eq!((sunrise.at.year(), sunrise.at.month()), (2025, 1));
+
+        let error = |config: &str| toml::from_str::<Schedule>(config).unwrap_err().to_string();
+        let themes = "light = \"a\"\ndark = \"b\"\n";
+        let both = format!(
+            "{}[times]\nlight = \"7:00\"\ndark = \"19:00\"\n[location]\nlatitude = 0.0\nlongitude = 0.0\n",
+            themes
+        );
+        assert!(error(&both).contains("not both"), "{}", error(&both));
+        let malformed = format!("{}[times]\nlight = \"7\"\ndark = \"19:00\"\n", themes);
+        assert!(error(&malformed).contains("times.light must be HH:MM"), "{}", error(&malformed));
+        let equal = format!("{}[times]\nlight = \"07:00\"\ndark = \"7:00\"\n", themes);
+        assert!(error(&equal).contains("must differ"), "{}", error(&equal));
+    }
+}
@@ -0,0 +1,115 @@
+//! Active theme selection
+//!
+//! Normally the `current/theme` symlink is the selection. On NixOS,
+//! home-manager makes that link point into the read-only nix store, where it
+//! can't be repointed, so the selection is kept in a writable state file
+//! (`$XDG_STATE_HOME/wonderland/current-theme.toml`) instead.
+
+use crate::loader::ThemeError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Default location of the nix store
+pub const NIX_STORE: &str = "/nix/store";
+
+/// Length of the hash prefix in store path names
+const STORE_HASH_LEN: usize = 32;
+
+/// Where the active theme is recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionBackend {
+    /// The `current/theme` symlink is repointed
+    Symlink,
+    /// The symlink is store-managed; the selection lives in a state file
+    StateFile,
+}
+
+/// Contents of the selection state file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SelectionState {
+    pub theme: String,
+    /// Where the store-managed link pointed when the selection was made. If
+    /// home-manager later repoints it, the declared theme wins again.
+    pub link_target: PathBuf,
+}
+
+impl SelectionState {
+    /// Read the state file; a missing or unreadable file means no selection
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        match toml::from_str(&content) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid theme state {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Write the state file atomically
+    pub(crate) fn save(&self, path: &Path) -> Result<(), ThemeError> {
+        let dir = path
+            .parent()
+            .ok_or_else(|| ThemeError::InvalidThemePath(path.to_path_buf()))?;
+        std::fs::create_dir_all(dir).map_err(|e| ThemeError::Io(dir.to_path_buf(), e))?;
+
+        let content =
+            toml::to_string(self).map_err(|e| ThemeError::Serialize(path.to_path_buf(), e))?;
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, content).map_err(|e| ThemeError::Io(tmp.clone(), e))?;
+        std::fs::rename(&tmp, path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            ThemeError::Io(path.to_path_buf(), e)
+        })
+    }
+}
+
+/// The nix store directory, honouring `NIX_STORE_DIR`
+pub fn store_dir() -> PathBuf {
+    std::env::var_os("NIX_STORE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(NIX_STORE))
+}
+
+/// Theme name for a theme directory path.
+///
+/// Direct children of the store are named `<hash>-<name>`; the hash is
+/// stripped, so `/nix/store/<hash>-tokyo-night` resolves to `tokyo-night`.
+pub fn theme_name_from_path(path: &Path, store: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+
+    if path.parent() == Some(store) {
+        if let Some((hash, rest)) = name.split_once('-') {
+            if is_store_hash(hash) && !rest.is_empty() {
+                return Some(rest.to_string());
+            }
+        }
+    }
+
+    Some(name.to_string())
+}
+
+/// Nix hashes are 32 characters of nix's base32 alphabet
+fn is_store_hash(hash: &str) -> bool {
+    hash.len() == STORE_HASH_LEN
+        && hash
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b.is_ascii_lowercase() && !b"eotu".contains(&b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_store_hashes() {
+        let store = Path::new(NIX_STORE);
+        let name = |path: &str| theme_name_from_path(Path::new(path), store).unwrap();
+
+        assert_eq!(name("/nix/store/0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-tokyo-night"), "tokyo-night");
+        assert_eq!(name("/nix/store/0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-themes/nord"), "nord");
+        assert_eq!(name("/home/me/.config/theme/themes/rose-pine"), "rose-pine");
+        // Only direct store children carry a hash
+        assert_eq!(name("/home/me/themes/0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-x"), "0c7gq4wqv2zwn8clh6v1r2bsvb2d2f8n-x");
+    }
+}
@@ -0,0 +1,311 @@
+//! ANSI terminal palette
+//!
+//! Every theme has a 16-color palette plus cursor and selection colors. It
+//! starts out derived from the color roles and is then overridden by the
+//! theme's alacritty.toml (or kitty.conf) and the `[terminal]` section of
+//! theme.toml, in that order.
+
+use crate::color::Color;
+use crate::loader::{Theme, ThemeError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Names of the eight normal ANSI colors, in palette order
+pub const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// A complete terminal palette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerminalPalette {
+    /// color0-color15: the normal colors followed by their bright variants
+    pub colors: [Color; 16],
+    pub cursor: Color,
+    pub cursor_text: Color,
+    pub selection_foreground: Color,
+    pub selection_background: Color,
+}
+
+impl TerminalPalette {
+    /// Build a palette from a theme's color roles
+    pub fn derive(theme: &Theme) -> Self {
+        let normal = [
+            theme.surface,
+            theme.error,
+            theme.success,
+            theme.warning,
+            theme.primary,
+            theme.secondary,
+            theme.border_active,
+            theme.text_muted,
+        ];
+
+        let mut colors = [Color::default(); 16];
+        for (i, color) in normal.iter().enumerate() {
+            colors[i] = *color;
+            colors[i + 8] = color.lighten(0.1);
+        }
+        colors[8] = theme.border;
+        colors[15] = theme.foreground;
+
+        TerminalPalette {
+            colors,
+            cursor: theme.foreground,
+            cursor_text: theme.background,
+            selection_foreground: theme.background,
+            selection_background: theme.primary,
+        }
+    }
+
+    /// Mix every color towards `other` in OKLab (t = 0.0 gives `self`)
+    pub fn lerp(&self, other: &TerminalPalette, t: f32) -> Self {
+        let mut colors = self.colors;
+        for (color, target) in colors.iter_mut().zip(&other.colors) {
+            *color = color.mix(target, t);
+        }
+
+        TerminalPalette {
+            colors,
+            cursor: self.cursor.mix(&other.cursor, t),
+            cursor_text: self.cursor_text.mix(&other.cursor_text, t),
+            selection_foreground: self.selection_foreground.mix(&other.selection_foreground, t),
+            selection_background: self.selection_background.mix(&other.selection_background, t),
+        }
+    }
+
+    pub fn black(&self) -> Color {
+        self.colors[0]
+    }
+
+    pub fn red(&self) -> Color {
+        self.colors[1]
+    }
+
+    pub fn green(&self) -> Color {
+        self.colors[2]
+    }
+
+    pub fn yellow(&self) -> Color {
+        self.colors[3]
+    }
+
+    pub fn blue(&self) -> Color {
+        self.colors[4]
+    }
+
+    pub fn magenta(&self) -> Color {
+        self.colors[5]
+    }
+
+    pub fn cyan(&self) -> Color {
+        self.colors[6]
+    }
+
+    pub fn white(&self) -> Color {
+        self.colors[7]
+    }
+
+    /// Look up `colorN`, an ANSI name (`red`, `bright_red`) or one of the
+    /// cursor/selection keys
+    pub fn get(&self, key: &str) -> Option<Color> {
+        if let Some(index) = key.strip_prefix("color") {
+            return index.parse::<usize>().ok().and_then(|i| self.colors.get(i).copied());
+        }
+        if let Some(i) = ANSI_NAMES.iter().position(|name| *name == key) {
+            return Some(self.colors[i]);
+        }
+        if let Some(i) = key
+            .strip_prefix("bright_")
+            .and_then(|name| ANSI_NAMES.iter().position(|n| *n == name))
+        {
+            return Some(self.colors[i + 8]);
+        }
+
+        match key {
+            "cursor" => Some(self.cursor),
+            "cursor_text" => Some(self.cursor_text),
+            "selection_foreground" => Some(self.selection_foreground),
+            "selection_background" => Some(self.selection_background),
+            _ => None,
+        }
+    }
+}
+
+/// A partial palette, as found in a terminal config or scheme file
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TerminalColors {
+    pub colors: [Option<Color>; 16],
+    pub cursor: Option<Color>,
+    pub cursor_text: Option<Color>,
+    pub selection_foreground: Option<Color>,
+    pub selection_background: Option<Color>,
+}
+
+impl TerminalColors {
+    /// Overlay the colors that are set onto `palette`
+    pub fn apply(&self, palette: &mut TerminalPalette) {
+        for (slot, color) in palette.colors.iter_mut().zip(self.colors) {
+            if let Some(color) = color {
+                *slot = color;
+            }
+        }
+
+        let fields = [
+            (&mut palette.cursor, self.cursor),
+            (&mut palette.cursor_text, self.cursor_text),
+            (&mut palette.selection_foreground, self.selection_foreground),
+            (&mut palette.selection_background, self.selection_background),
+        ];
+        for (slot, color) in fields {
+            if let Some(color) = color {
+                *slot = color;
+            }
+        }
+    }
+
+    /// Read the `[colors]` table of an Alacritty config
+    pub(crate) fn from_alacritty(colors: &toml::Value) -> Result<Self, String> {
+        let color = |section: &str, key: &str| -> Result<Option<Color>, String> {
+            match colors.get(section).and_then(|s| s.get(key)).and_then(|v| v.as_str()) {
+                Some(value) => parse_color(value),
+                None => Ok(None),
+            }
+        };
+
+        let mut terminal = TerminalColors::default();
+        for (i, name) in ANSI_NAMES.iter().enumerate() {
+            terminal.colors[i] = color("normal", name)?;
+            terminal.colors[i + 8] = color("bright", name)?;
+        }
+        terminal.cursor = color("cursor", "cursor")?;
+        terminal.cursor_text = color("cursor", "text")?;
+        terminal.selection_foreground = color("selection", "text")?;
+        terminal.selection_background = color("selection", "background")?;
+
+        Ok(terminal)
+    }
+
+    /// Read the color keys of a kitty.conf, as returned by [`kitty_entries`]
+    pub(crate) fn from_kitty(entries: &BTreeMap<String, String>) -> Result<Self, String> {
+        let color = |key: &str| match entries.get(key) {
+            Some(value) => parse_color(value),
+            None => Ok(None),
+        };
+
+        let mut terminal = TerminalColors::default();
+        for (i, slot) in terminal.colors.iter_mut().enumerate() {
+            *slot = color(&format!("color{}", i))?;
+        }
+        terminal.cursor = color("cursor")?;
+        terminal.cursor_text = color("cursor_text_color")?;
+        terminal.selection_foreground = color("selection_foreground")?;
+        terminal.selection_background = color("selection_background")?;
+
+        Ok(terminal)
+    }
+
+    /// Read the palette a theme directory ships: alacritty.toml if present,
+    /// otherwise kitty.conf
+    pub(crate) fn from_theme_dir(dir: &Path) -> Result<Option<Self>, ThemeError> {
+        let alacritty = dir.join("alacritty.toml");
+        if alacritty.exists() {
+            let content = read(&alacritty)?;
+            let doc: toml::Value = toml::from_str(&content)
+                .map_err(|e| ThemeError::Terminal(alacritty.clone(), e.to_string()))?;
+            let Some(colors) = doc.get("colors") else {
+                return Ok(None);
+            };
+            return Self::from_alacritty(colors)
+                .map(Some)
+                .map_err(|e| ThemeError::Terminal(alacritty, e));
+        }
+
+        let kitty = dir.join("kitty.conf");
+        if kitty.exists() {
+            let content = read(&kitty)?;
+            return Self::from_kitty(&kitty_entries(&content))
+                .map(Some)
+                .map_err(|e| ThemeError::Terminal(kitty, e));
+        }
+
+        Ok(None)
+    }
+}
+
+fn read(path: &Path) -> Result<String, ThemeError> {
+    std::fs::read_to_string(path).map_err(|e| ThemeError::Io(path.to_path_buf(), e))
+}
+
+/// Split a kitty.conf into `key value` pairs, skipping comments
+pub(crate) fn kitty_entries(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Parse a terminal config color: `#rrggbb`, `rrggbb` or `0xrrggbb`.
+///
+/// Keywords such as Alacritty's `CellForeground` or kitty's `none` mean
+/// "no fixed color" and yield `None`.
+pub(crate) fn parse_color(value: &str) -> Result<Option<Color>, String> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    let hex = match value.strip_prefix("0x") {
+        Some(hex) => hex,
+        None if value.starts_with('#') => value,
+        None if value.len() == 6 && value.chars().all(|c| c.is_ascii_hexdigit()) => value,
+        None => return Ok(None),
+    };
+
+    Color::from_hex(hex)
+        .map(Some)
+        .map_err(|e| format!("invalid color `{}`: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_alacritty_and_kitty() {
+        let doc: toml::Value = toml::from_str(
+            "[colors.normal]\nred = '0xf7768e'\n[colors.bright]\nblack = '#414868'\n\
+             [colors.cursor]\ncursor = 'CellForeground'\n",
+        )
+        .unwrap();
+        let alacritty = TerminalColors::from_alacritty(&doc["colors"]).unwrap();
+        assert_eq!(alacritty.colors[1], Color::from_hex("#f7768e").ok());
+        assert_eq!(alacritty.colors[8], Color::from_hex("#414868").ok());
+        assert_eq!(alacritty.cursor, None);
+
+        let kitty = TerminalColors::from_kitty(&kitty_entries(
+            "# Tokyo Night\ncolor2  #9ece6a\nselection_foreground none\n",
+        ))
+        .unwrap();
+        assert_eq!(kitty.colors[2], Color::from_hex("#9ece6a").ok());
+        assert_eq!(kitty.selection_foreground, None);
+
+        assert!(TerminalColors::from_kitty(&kitty_entries("color1 #é12\n")).is_err());
+    }
+
+    #[test]
+    fn overrides_derived_palette() {
+        let theme = Theme::derive_extended_colors(
+            Color::from_hex("#c0caf5").unwrap(),
+            Color::from_hex("#1a1b26").unwrap(),
+        );
+        let mut palette = TerminalPalette::derive(&theme);
+        assert_eq!(palette.red(), theme.error);
+
+        let mut colors = TerminalColors::default();
+        colors.colors[1] = Color::from_hex("#f7768e").ok();
+        colors.apply(&mut palette);
+
+        assert_eq!(palette.get("red"), Color::from_hex("#f7768e").ok());
+        assert_eq!(palette.get("color15"), Some(theme.foreground));
+        assert_eq!(palette.get("bright_black"), Some(theme.border));
+    }
+}
@@ -0,0 +1,501 @@
+//! theme.toml schema
+//!
+//! A theme directory may contain a `theme.toml` that carries metadata and
+//! overrides any of the derived color roles:
+//!
+//! ```toml
+//! [meta]
+//! name = "Tokyo Night"
+//! author = "enkia"
+//! variant = "dark"
+//!
+//! [colors]
+//! primary = "#7aa2f7"
+//! error = "#f7768e"
+//!
+//! [terminal]
+//! cursor = "#c0caf5"
+//! colors = ["#15161e", "#f7768e", ...] # all 16, color0 first
+//!
+//! [tokens.radius]
+//! large = 12
+//! ```
+//!
+//! See [`Tokens`] for every `[tokens]` key.
+//!
+//! A theme can inherit from another one and only override what differs,
+//! and can carry its other variant under the same name:
+//!
+//! ```toml
+//! extends = "gruvbox"
+//!
+//! [colors]
+//! primary = "#fe8019"
+//!
+//! [variants.light.colors]
+//! background = "#fbf1c7"
+//! foreground = "#3c3836"
+//! ```
+//!
+//! Parents are looked up by name among the installed themes, then the
+//! built-in ones. Only theme.toml is inherited. Tables are merged key by
+//! key, so a child can override a single token or color. The requested
+//! variant's tables are merged after the whole chain, parent first, and set
+//! `meta.variant`.
+
+use crate::color::Color;
+use crate::loader::{validate_name, Role, Theme, ThemeError};
+use crate::terminal::{TerminalColors, TerminalPalette};
+use crate::tokens::Tokens;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Parsed contents of a theme's `theme.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeFile {
+    /// Theme this one inherits from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(default)]
+    pub meta: MetaSection,
+    #[serde(default)]
+    pub colors: ColorOverrides,
+    #[serde(default, skip_serializing_if = "TerminalSection::is_empty")]
+    pub terminal: TerminalSection,
+    #[serde(default)]
+    pub tokens: Tokens,
+    /// Partial theme.toml tables applied when a variant is requested
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<Variant, toml::Table>,
+}
+
+/// `[meta]` section of theme.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetaSection {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub variant: Option<Variant>,
+}
+
+/// `[colors]` section of theme.toml
+///
+/// Every role is optional; roles left out keep their derived value. Values
+/// accept any CSS color syntax, e.g. `"#7aa2f7"` or `"rgba(122, 162, 247, 0.8)"`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorOverrides {
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub primary: Option<String>,
+    pub secondary: Option<String>,
+    pub surface: Option<String>,
+    pub error: Option<String>,
+    pub warning: Option<String>,
+    pub success: Option<String>,
+    pub border: Option<String>,
+    pub border_active: Option<String>,
+    pub text_muted: Option<String>,
+}
+
+/// `[terminal]` section of theme.toml
+///
+/// Overrides the palette read from the theme's alacritty.toml or kitty.conf.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerminalSection {
+    /// color0-color15
+    pub colors: Option<[String; 16]>,
+    pub cursor: Option<String>,
+    pub cursor_text: Option<String>,
+    pub selection_foreground: Option<String>,
+    pub selection_background: Option<String>,
+}
+
+/// Light or dark theme variant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Theme metadata, resolved from theme.toml with sensible defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeMeta {
+    /// Human readable name, defaults to the directory name
+    pub display_name: String,
+    pub author: Option<String>,
+    pub variant: Variant,
+}
+
+impl ThemeFile {
+    /// Read and parse a theme.toml file
+    pub fn load(path: &Path) -> Result<Self, ThemeError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ThemeError::Io(path.to_path_buf(), e))?;
+        Self::parse(&content, path)
+    }
+
+    /// Parse theme.toml content; `path` is only used for error reporting
+    pub fn parse(content: &str, path: &Path) -> Result<Self, ThemeError> {
+        toml::from_str(content).map_err(|e| ThemeError::Toml(path.to_path_buf(), e))
+    }
+
+    /// Parse theme.toml content with its `extends` chain and, if given, the
+    /// `variant` table merged in. `lookup` returns the theme.toml content
+    /// and path of the parent a theme (first argument) extends (second).
+    pub(crate) fn resolve<F>(
+        name: &str,
+        content: &str,
+        path: &Path,
+        variant: Option<Variant>,
+        lookup: &F,
+    ) -> Result<Self, ThemeError>
+    where
+        F: Fn(&str, &str) -> Result<Option<(String, PathBuf)>, ThemeError>,
+    {
+        let mut overlays = Vec::new();
+        let mut table = resolve_table(name, content, path, variant, lookup, &mut Vec::new(), &mut overlays)?;
+
+        // Variant tables go over the plain tables of the whole chain, so a
+        // child's `[colors]` doesn't hide its parent's `[variants.light]`
+        if let Some(variant) = variant.filter(|_| !overlays.is_empty()) {
+            for overlay in overlays {
+                merge(&mut table, overlay);
+            }
+            let meta = table
+                .entry("meta")
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let toml::Value::Table(meta) = meta {
+                let value = toml::Value::try_from(variant).expect("variants serialize to strings");
+                meta.insert("variant".to_string(), value);
+            }
+        }
+
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| ThemeError::Toml(path.to_path_buf(), e))
+    }
+
+    /// Describe a loaded theme completely, with every role set
+    pub fn from_theme(theme: &Theme) -> Self {
+        let mut colors = ColorOverrides::default();
+        for role in Role::ALL {
+            *colors.get_mut(role) = Some(theme.color(role).to_hex());
+        }
+
+        ThemeFile {
+            extends: None,
+            meta: MetaSection {
+                name: Some(theme.meta.display_name.clone()),
+                author: theme.meta.author.clone(),
+                variant: Some(theme.meta.variant),
+            },
+            colors,
+            terminal: TerminalSection::from_palette(&theme.terminal),
+            tokens: theme.tokens.clone(),
+            variants: BTreeMap::new(),
+        }
+    }
+
+    /// Parse the `foreground` and `background` overrides, if present
+    pub fn base_colors(&self, path: &Path) -> Result<(Option<Color>, Option<Color>), ThemeError> {
+        Ok((
+            parse_role(path, Role::Foreground, self.colors.get(Role::Foreground))?,
+            parse_role(path, Role::Background, self.colors.get(Role::Background))?,
+        ))
+    }
+
+    /// Apply metadata, color overrides and tokens on top of a derived theme
+    pub fn apply(&self, theme: &mut Theme, path: &Path) -> Result<(), ThemeError> {
+        for role in Role::ALL {
+            if matches!(role, Role::Foreground | Role::Background) {
+                continue;
+            }
+            if let Some(color) = parse_role(path, role, self.colors.get(role))? {
+                *theme.color_mut(role) = color;
+            }
+        }
+
+        if let Some(name) = &self.meta.name {
+            theme.meta.display_name = name.clone();
+        }
+        if let Some(author) = &self.meta.author {
+            theme.meta.author = Some(author.clone());
+        }
+        if let Some(variant) = self.meta.variant {
+            theme.meta.variant = variant;
+        }
+
+        theme.tokens = self.tokens.clone();
+
+        Ok(())
+    }
+
+    /// Apply the `[terminal]` overrides on top of a palette
+    pub fn apply_terminal(&self, palette: &mut TerminalPalette, path: &Path) -> Result<(), ThemeError> {
+        self.terminal.parse(path)?.apply(palette);
+        Ok(())
+    }
+}
+
+impl TerminalSection {
+    fn from_palette(palette: &TerminalPalette) -> Self {
+        TerminalSection {
+            colors: Some(palette.colors.map(|color| color.to_hex())),
+            cursor: Some(palette.cursor.to_hex()),
+            cursor_text: Some(palette.cursor_text.to_hex()),
+            selection_foreground: Some(palette.selection_foreground.to_hex()),
+            selection_background: Some(palette.selection_background.to_hex()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.colors.is_none()
+            && self.cursor.is_none()
+            && self.cursor_text.is_none()
+            && self.selection_foreground.is_none()
+            && self.selection_background.is_none()
+    }
+
+    fn parse(&self, path: &Path) -> Result<TerminalColors, ThemeError> {
+        let mut terminal = TerminalColors::default();
+        if let Some(colors) = &self.colors {
+            for (i, value) in colors.iter().enumerate() {
+                terminal.colors[i] = parse_value(path, &format!("terminal.colors[{}]", i), Some(value))?;
+            }
+        }
+        terminal.cursor = parse_value(path, "terminal.cursor", self.cursor.as_deref())?;
+        terminal.cursor_text = parse_value(path, "terminal.cursor_text", self.cursor_text.as_deref())?;
+        terminal.selection_foreground = parse_value(
+            path,
+            "terminal.selection_foreground",
+            self.selection_foreground.as_deref(),
+        )?;
+        terminal.selection_background = parse_value(
+            path,
+            "terminal.selection_background",
+            self.selection_background.as_deref(),
+        )?;
+
+        Ok(terminal)
+    }
+}
+
+impl ColorOverrides {
+    /// Raw value set for a role, if any
+    pub fn get(&self, role: Role) -> Option<&str> {
+        match role {
+            Role::Foreground => self.foreground.as_deref(),
+            Role::Background => self.background.as_deref(),
+            Role::Primary => self.primary.as_deref(),
+            Role::Secondary => self.secondary.as_deref(),
+            Role::Surface => self.surface.as_deref(),
+            Role::Error => self.error.as_deref(),
+            Role::Warning => self.warning.as_deref(),
+            Role::Success => self.success.as_deref(),
+            Role::Border => self.border.as_deref(),
+            Role::BorderActive => self.border_active.as_deref(),
+            Role::TextMuted => self.text_muted.as_deref(),
+        }
+    }
+
+    fn get_mut(&mut self, role: Role) -> &mut Option<String> {
+        match role {
+            Role::Foreground => &mut self.foreground,
+            Role::Background => &mut self.background,
+            Role::Primary => &mut self.primary,
+            Role::Secondary => &mut self.secondary,
+            Role::Surface => &mut self.surface,
+            Role::Error => &mut self.error,
+            Role::Warning => &mut self.warning,
+            Role::Success => &mut self.success,
+            Role::Border => &mut self.border,
+            Role::BorderActive => &mut self.border_active,
+            Role::TextMuted => &mut self.text_muted,
+        }
+    }
+
+    /// Roles that are explicitly set
+    pub fn roles(&self) -> impl Iterator<Item = Role> + '_ {
+        Role::ALL
+            .into_iter()
+            .filter(|role| self.get(*role).is_some())
+    }
+}
+
+/// Merged theme.toml table of `name` and its ancestors. `chain` holds the
+/// themes being resolved, to catch cycles.
+fn resolve_table<F>(
+    name: &str,
+    content: &str,
+    path: &Path,
+    variant: Option<Variant>,
+    lookup: &F,
+    chain: &mut Vec<(String, PathBuf)>,
+    overlays: &mut Vec<toml::Table>,
+) -> Result<toml::Table, ThemeError>
+where
+    F: Fn(&str, &str) -> Result<Option<(String, PathBuf)>, ThemeError>,
+{
+    // Compared by path, as an installed theme may extend the built-in one
+    // of the same name
+    chain.push((name.to_string(), path.to_path_buf()));
+    if chain[..chain.len() - 1].iter().any(|(_, seen)| seen == path) {
+        let names = chain.iter().map(|(name, _)| name.clone()).collect();
+        return Err(ThemeError::InheritanceCycle(names));
+    }
+
+    // Parse typed first for precise errors, then merge the raw tables so
+    // only keys that are actually written override the parent
+    let file = ThemeFile::parse(content, path)?;
+    let mut table: toml::Table =
+        toml::from_str(content).map_err(|e| ThemeError::Toml(path.to_path_buf(), e))?;
+    table.remove("extends");
+    table.remove("variants");
+
+    let mut merged = match &file.extends {
+        Some(parent) => {
+            // Parents are looked up as directories in the themes dir
+            validate_name(parent)?;
+            let (content, parent_path) = lookup(name, parent)?
+                .ok_or_else(|| ThemeError::UnknownParent(name.to_string(), parent.clone()))?;
+            resolve_table(parent, &content, &parent_path, variant, lookup, chain, overlays)?
+        }
+        None => toml::Table::new(),
+    };
+    merge(&mut merged, table);
+
+    if let Some(overlay) = variant.and_then(|variant| file.variants.get(&variant)) {
+        overlays.push(overlay.clone());
+    }
+
+    chain.pop();
+    Ok(merged)
+}
+
+/// Merge `overlay` into `base`, recursing into tables present in both
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn parse_role(path: &Path, role: Role, value: Option<&str>) -> Result<Option<Color>, ThemeError> {
+    parse_value(path, &format!("colors.{}", role), value)
+}
+
+fn parse_value(path: &Path, key: &str, value: Option<&str>) -> Result<Option<Color>, ThemeError> {
+    value
+        .map(|v| {
+            v.parse::<Color>()
+                .map_err(|source| ThemeError::InvalidColor {
+                    path: path.to_path_buf(),
+                    key: key.to_string(),
+                    source,
+                })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_roles_and_meta() {
+        let path = Path::new("theme.toml");
+        let file = ThemeFile::parse(
+            r##"
+            [meta]
+            name = "Tokyo Night"
+            variant = "dark"
+
+            [colors]
+            primary = "#7aa2f7"
+            "##,
+            path,
+        )
+        .unwrap();
+
+        let fg = Color::from_hex("#c0caf5").unwrap();
+        let bg = Color::from_hex("#1a1b26").unwrap();
+        let mut theme = Theme::derive_extended_colors(fg, bg);
+        file.apply(&mut theme, path).unwrap();
+
+        assert_eq!(theme.primary, Color::from_hex("#7aa2f7").unwrap());
+        assert_eq!(theme.meta.display_name, "Tokyo Night");
+        assert_eq!(theme.meta.variant, Variant::Dark);
+    }
+
+    #[test]
+    fn bad_color_names_the_key() {
+        let path = Path::new("theme.toml");
+        let file = ThemeFile::parse("[colors]\nsurface = \"#12\"\n", path).unwrap();
+        let fg = Color::from_hex("#ffffff").unwrap();
+        let mut theme = Theme::derive_extended_colors(fg, Color::default());
+
+        let err = file.apply(&mut theme, path).unwrap_err();
+        assert!(matches!(err, ThemeError::InvalidColor { ref key, .. } if key == "colors.surface"));
+    }
+
+    #[test]
+    fn extends_merges_parents_and_variants() {
+        let sources: BTreeMap<&str, &str> = BTreeMap::from([
+            (
+                "base",
+                "[meta]\nauthor = \"me\"\n[colors]\nbackground = \"#282828\"\nprimary = \"#83a598\"\n\
+                 [tokens.radius]\nlarge = 0\n\
+                 [variants.light.colors]\nbackground = \"#fbf1c7\"\n",
+            ),
+            ("loop-a", "extends = \"loop-b\""),
+            ("loop-b", "extends = \"loop-a\""),
+        ]);
+        let lookup = |_: &str, name: &str| {
+            Ok(sources
+                .get(name)
+                .map(|content| (content.to_string(), PathBuf::from(name))))
+        };
+        let child = "extends = \"base\"\n[colors]\nprimary = \"#fe8019\"\n[tokens.radius]\nsmall = 1\n";
+        let path = Path::new("child");
+
+        let file = ThemeFile::resolve("child", child, path, None, &lookup).unwrap();
+        assert_eq!(file.colors.primary.as_deref(), Some("#fe8019"));
+        assert_eq!(file.colors.background.as_deref(), Some("#282828"));
+        assert_eq!(file.meta.author.as_deref(), Some("me"));
+        assert_eq!((file.tokens.radius.small, file.tokens.radius.large), (1.0, 0.0));
+
+        let light = ThemeFile::resolve("child", child, path, Some(Variant::Light), &lookup).unwrap();
+        assert_eq!(light.colors.background.as_deref(), Some("#fbf1c7"));
+        assert_eq!(light.meta.variant, Some(Variant::Light));
+
+        let err = ThemeFile::resolve("child", "extends = \"loop-a\"", path, None, &lookup).unwrap_err();
+        assert!(matches!(err, ThemeError::InheritanceCycle(ref chain) if chain.len() == 4));
+        let err = ThemeFile::resolve("child", "extends = \"nope\"", path, None, &lookup).unwrap_err();
+        assert!(matches!(err, ThemeError::UnknownParent(..)));
+        let err = ThemeFile::resolve("child", "extends = \"../../x\"", path, None, &lookup).unwrap_err();
+        assert!(matches!(err, ThemeError::InvalidName(..)));
+
+        // The parent's light background beats the child's plain one
+        let dark_child = "extends = \"base\"\n[colors]\nbackground = \"#1d2021\"\n";
+        let light = ThemeFile::resolve("child", dark_child, path, Some(Variant::Light), &lookup).unwrap();
+        assert_eq!(light.colors.background.as_deref(), Some("#fbf1c7"));
+        let dark = ThemeFile::resolve("child", dark_child, path, Some(Variant::Dark), &lookup).unwrap();
+        assert_eq!(dark.colors.background.as_deref(), Some("#1d2021"));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let err = ThemeFile::parse("[colors]\nprimry = \"#fff\"\n", Path::new("theme.toml"));
+        assert!(matches!(err, Err(ThemeError::Toml(..))));
+    }
+}
@@ -0,0 +1,252 @@
+//! Design tokens
+//!
+//! Non-color theme values: corner radii, spacing, typography, border widths
+//! and shadow. Loaded from the `[tokens]` section of theme.toml; anything
+//! left out keeps the defaults below, which match the original hardcoded
+//! Wonderland geometry.
+//!
+//! ```toml
+//! [tokens.radius]
+//! small = 0
+//! medium = 0
+//! large = 0
+//!
+//! [tokens.font]
+//! family = "JetBrainsMono Nerd Font"
+//! size = 13
+//! ```
+
+use crate::color::Color;
+use serde::{Deserialize, Serialize};
+
+/// All design tokens of a theme
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tokens {
+    pub radius: Radii,
+    pub spacing: Spacing,
+    pub font: Typography,
+    pub border: BorderWidths,
+    pub shadow: Shadow,
+}
+
+/// Corner radius scale
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Radii {
+    /// Scrollbars, tags
+    pub small: f32,
+    /// Buttons, inputs
+    pub medium: f32,
+    /// Cards, panels, windows
+    pub large: f32,
+}
+
+impl Default for Radii {
+    fn default() -> Self {
+        Self {
+            small: 4.0,
+            medium: 6.0,
+            large: 8.0,
+        }
+    }
+}
+
+/// Spacing scale for padding and gaps
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spacing {
+    pub xs: f32,
+    pub sm: f32,
+    pub md: f32,
+    pub lg: f32,
+    pub xl: f32,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            xs: 2.0,
+            sm: 4.0,
+            md: 8.0,
+            lg: 12.0,
+            xl: 16.0,
+        }
+    }
+}
+
+/// Font families and sizes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Typography {
+    /// UI font; the system default when unset
+    pub family: Option<String>,
+    /// Font for code and terminals
+    pub monospace: Option<String>,
+    /// Body text size
+    pub size: f32,
+    pub size_small: f32,
+    pub size_large: f32,
+    pub size_title: f32,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            family: None,
+            monospace: None,
+            size: 14.0,
+            size_small: 12.0,
+            size_large: 16.0,
+            size_title: 20.0,
+        }
+    }
+}
+
+/// Border widths
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BorderWidths {
+    /// Resting borders
+    pub thin: f32,
+    /// Focus rings and active outlines
+    pub thick: f32,
+}
+
+impl Default for BorderWidths {
+    fn default() -> Self {
+        Self {
+            thin: 1.0,
+            thick: 2.0,
+        }
+    }
+}
+
+/// Drop shadow of raised surfaces; fully transparent by default
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Shadow {
+    pub color: Color,
+    /// Horizontal and vertical offset
+    pub offset: [f32; 2],
+    pub blur: f32,
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            color: Color::new(0.0, 0.0, 0.0, 0.0),
+            offset: [0.0, 0.0],
+            blur: 0.0,
+        }
+    }
+}
+
+impl Shadow {
+    #[cfg(feature = "iced")]
+    pub fn to_iced(&self) -> iced::Shadow {
+        iced::Shadow {
+            color: self.color.to_iced(),
+            offset: iced::Vector::new(self.offset[0], self.offset[1]),
+            blur_radius: self.blur,
+        }
+    }
+}
+
+impl Tokens {
+    /// Interpolate every size towards `other` (t = 0.0 gives `self`); font
+    /// families switch over halfway
+    pub fn lerp(&self, other: &Tokens, t: f32) -> Tokens {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let fonts = if t < 0.5 { &self.font } else { &other.font };
+
+        Tokens {
+            radius: Radii {
+                small: mix(self.radius.small, other.radius.small),
+                medium: mix(self.radius.medium, other.radius.medium),
+                large: mix(self.radius.large, other.radius.large),
+            },
+            spacing: Spacing {
+                xs: mix(self.spacing.xs, other.spacing.xs),
+                sm: mix(self.spacing.sm, other.spacing.sm),
+                md: mix(self.spacing.md, other.spacing.md),
+                lg: mix(self.spacing.lg, other.spacing.lg),
+                xl: mix(self.spacing.xl, other.spacing.xl),
+            },
+            font: Typography {
+                family: fonts.family.clone(),
+                monospace: fonts.monospace.clone(),
+                size: mix(self.font.size, other.font.size),
+                size_small: mix(self.font.size_small, other.font.size_small),
+                size_large: mix(self.font.size_large, other.font.size_large),
+                size_title: mix(self.font.size_title, other.font.size_title),
+            },
+            border: BorderWidths {
+                thin: mix(self.border.thin, other.border.thin),
+                thick: mix(self.border.thick, other.border.thick),
+            },
+            shadow: Shadow {
+                color: self.shadow.color.mix(&other.shadow.color, t),
+                offset: [
+                    mix(self.shadow.offset[0], other.shadow.offset[0]),
+                    mix(self.shadow.offset[1], other.shadow.offset[1]),
+                ],
+                blur: mix(self.shadow.blur, other.shadow.blur),
+            },
+        }
+    }
+
+    /// Look up a token by dotted path, e.g. `radius.medium` or `font.family`,
+    /// formatted for config files
+    pub fn get(&self, path: &str) -> Option<String> {
+        let number = |n: f32| {
+            if n.fract() == 0.0 {
+                format!("{}", n as i64)
+            } else {
+                format!("{}", n)
+            }
+        };
+
+        Some(match path {
+            "radius.small" => number(self.radius.small),
+            "radius.medium" => number(self.radius.medium),
+            "radius.large" => number(self.radius.large),
+            "spacing.xs" => number(self.spacing.xs),
+            "spacing.sm" => number(self.spacing.sm),
+            "spacing.md" => number(self.spacing.md),
+            "spacing.lg" => number(self.spacing.lg),
+            "spacing.xl" => number(self.spacing.xl),
+            "font.family" => self.font.family.clone().unwrap_or_default(),
+            "font.monospace" => self.font.monospace.clone().unwrap_or_default(),
+            "font.size" => number(self.font.size),
+            "font.size_small" => number(self.font.size_small),
+            "font.size_large" => number(self.font.size_large),
+            "font.size_title" => number(self.font.size_title),
+            "border.thin" => number(self.border.thin),
+            "border.thick" => number(self.border.thick),
+            "shadow.blur" => number(self.shadow.blur),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_sections_keep_defaults() {
+        let tokens: Tokens = toml::from_str(
+            "[radius]\nlarge = 0\n[font]\nfamily = \"Inter\"\n[shadow]\ncolor = \"#00000066\"\nblur = 12\n",
+        )
+        .unwrap();
+
+        assert_eq!(tokens.radius.large, 0.0);
+        assert_eq!(tokens.radius.medium, 6.0);
+        assert_eq!(tokens.font.family.as_deref(), Some("Inter"));
+        assert_eq!(tokens.shadow.color.to_rgba8()[3], 0x66);
+        assert_eq!(tokens.get("radius.large").as_deref(), Some("0"));
+        assert_eq!(tokens.get("shadow.blur").as_deref(), Some("12"));
+    }
+}
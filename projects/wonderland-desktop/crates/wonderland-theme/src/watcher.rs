@@ -0,0 +1,277 @@
+//! Hot-reload support
+//!
+//! Watches the `current/theme` symlink, the selection state file and the
+//! files of the active theme, and emits a freshly loaded [`Theme`] whenever
+//! any of them changes.
+
+use crate::loader::{Theme, ThemeError, ThemeLoader};
+use futures::channel::mpsc;
+use futures::SinkExt;
+#[cfg(feature = "iced")]
+use futures::Stream;
+#[cfg(feature = "iced")]
+use iced::Subscription;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default quiet period before a burst of file events triggers a reload
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Watches the active theme and reloads it on change
+#[derive(Clone)]
+pub struct ThemeWatcher {
+    loader: ThemeLoader,
+    debounce: Duration,
+}
+
+impl ThemeWatcher {
+    /// Create a watcher for the loader's current theme
+    pub fn new(loader: ThemeLoader) -> Self {
+        Self {
+            loader,
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+
+    /// Set how long the files must stay quiet before reloading
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Watch for changes, sending each reloaded theme to `output`.
+    ///
+    /// Runs until `output` is closed. Must be polled on a tokio runtime.
+    pub async fn run(self, mut output: mpsc::Sender<Theme>) -> Result<(), ThemeError> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(ThemeError::Watch)?;
+
+        // The symlink itself is replaced on switch, so watch its directory
+        let current_link = self.loader.current_link().to_path_buf();
+        let link_dir = current_link
+            .parent()
+            .ok_or_else(|| ThemeError::InvalidThemePath(current_link.clone()))?
+            .to_path_buf();
+        watcher
+            .watch(&link_dir, RecursiveMode::NonRecursive)
+            .map_err(ThemeError::Watch)?;
+
+        // Store-managed links record the selection in a state file instead
+        let state_file = self.loader.state_file().to_path_buf();
+        if let Some(state_dir) = state_file.parent() {
+            let watched = std::fs::create_dir_all(state_dir)
+                .map_err(notify::Error::io)
+                .and_then(|()| watcher.watch(state_dir, RecursiveMode::NonRecursive));
+            if let Err(e) = watched {
+                tracing::warn!("Failed to watch {}: {}", state_dir.display(), e);
+            }
+        }
+        let paths = WatchedPaths {
+            current_link,
+            link_dir,
+            state_file,
+        };
+
+        let mut theme_dir = self.rewatch(&mut watcher, None);
+
+        while let Some(event) = rx.recv().await {
+            let mut relevant = paths.is_relevant(event);
+
+            // Collapse bursts (e.g. a theme script rewriting several files)
+            loop {
+                match tokio::time::timeout(self.debounce, rx.recv()).await {
+                    Ok(Some(event)) => relevant |= paths.is_relevant(event),
+                    Ok(None) => return Ok(()),
+                    Err(_) => break,
+                }
+            }
+
+            if !relevant {
+                continue;
+            }
+
+            theme_dir = self.rewatch(&mut watcher, theme_dir);
+
+            match self.loader.load_current() {
+                Ok(theme) => {
+                    tracing::info!("Theme changed: {}", theme.name);
+                    if output.send(theme).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to reload theme: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stream of reloaded themes
+    #[cfg(feature = "iced")]
+    pub fn into_stream(self) -> impl Stream<Item = Theme> {
+        iced::stream::channel(16, |output| async move {
+            if let Err(e) = self.run(output).await {
+                tracing::error!("Theme watcher stopped: {}", e);
+            }
+        })
+    }
+
+    /// Iced subscription that yields the new theme after every change
+    #[cfg(feature = "iced")]
+    pub fn subscription(self) -> Subscription<Theme> {
+        let id = self.loader.current_link().to_path_buf();
+        Subscription::run_with_id(id, self.into_stream())
+    }
+
+    /// Point the recursive watch at the currently active theme directory
+    fn rewatch(
+        &self,
+        watcher: &mut RecommendedWatcher,
+        previous: Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        let current = self.loader.current_theme_dir().ok();
+
+        if current == previous {
+            return previous;
+        }
+
+        if let Some(old) = &previous {
+            let _ = watcher.unwatch(old);
+        }
+
+        match &current {
+            Some(dir) => match watcher.watch(dir, RecursiveMode::Recursive) {
+                Ok(()) => current,
+                Err(e) => {
+                    tracing::warn!("Failed to watch {}: {}", dir.display(), e);
+                    None
+                }
+            },
+            None => None,
+        }
+    }
+}
+
+/// Iced subscription watching the default theme location
+#[cfg(feature = "iced")]
+pub fn subscription() -> Subscription<Theme> {
+    Subscription::run(default_stream)
+}
+
+#[cfg(feature = "iced")]
+fn default_stream() -> impl Stream<Item = Theme> {
+    iced::stream::channel(16, |output| async move {
+        let result = match ThemeLoader::new() {
+            Ok(loader) => ThemeWatcher::new(loader).run(output).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            tracing::error!("Theme watcher stopped: {}", e);
+        }
+    })
+}
+
+/// Paths watched non-recursively, whose siblings must be ignored
+struct WatchedPaths {
+    current_link: PathBuf,
+    link_dir: PathBuf,
+    state_file: PathBuf,
+}
+
+impl WatchedPaths {
+    /// Whether a file event can affect the loaded theme
+    fn is_relevant(&self, event: notify::Result<Event>) -> bool {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Theme watch error: {}", e);
+                return false;
+            }
+        };
+
+        if event.kind.is_access() {
+            return false;
+        }
+
+        // Ignore siblings of the symlink and state file, like current/background
+        let state_dir = self.state_file.parent();
+        event.paths.iter().any(|path| {
+            let parent = path.parent();
+            if path == &self.current_link || path == &self.state_file {
+                true
+            } else {
+                parent != Some(self.link_dir.as_path()) && parent != state_dir
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+    use notify::EventKind;
+
+    fn event(kind: EventKind, path: PathBuf) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(path))
+    }
+
+    #[test]
+    fn ignores_unrelated_events() {
+        let root = PathBuf::from("/wonderland");
+        let paths = WatchedPaths {
+            current_link: root.join("current/theme"),
+            link_dir: root.join("current"),
+            state_file: root.join("state/current-theme.toml"),
+        };
+        let modify = EventKind::Modify(ModifyKind::Any);
+
+        assert!(paths.is_relevant(event(EventKind::Create(CreateKind::Any), root.join("current/theme"))));
+        assert!(paths.is_relevant(event(modify, root.join("state/current-theme.toml"))));
+        assert!(paths.is_relevant(event(modify, root.join("themes/nord/waybar.css"))));
+        assert!(!paths.is_relevant(event(modify, root.join("current/background"))));
+        assert!(!paths.is_relevant(event(modify, root.join("state/schedule.toml"))));
+        assert!(!paths.is_relevant(event(EventKind::Access(AccessKind::Any), root.join("themes/nord/waybar.css"))));
+        assert!(!paths.is_relevant(Err(notify::Error::generic("watch lost"))));
+    }
+
+    #[tokio::test]
+    async fn reloads_once_per_burst() {
+        let root = tempfile::tempdir().unwrap();
+        let theme_dir = root.path().join("themes/nord");
+        let waybar_css = theme_dir.join("waybar.css");
+        let write_css = |background: &str| {
+            let css = format!("@define-color foreground #d8dee9;\n@define-color background {};\n", background);
+            std::fs::write(&waybar_css, css).unwrap();
+        };
+        std::fs::create_dir_all(&theme_dir).unwrap();
+        write_css("#2e3440");
+        std::fs::create_dir_all(root.path().join("current")).unwrap();
+        std::os::unix::fs::symlink(&theme_dir, root.path().join("current/theme")).unwrap();
+
+        let loader = ThemeLoader::with_paths(root.path().join("themes"), root.path().join("current/theme"))
+            .with_state_file(root.path().join("state/current-theme.toml"));
+        let (output, mut themes) = mpsc::channel(16);
+        let watcher = ThemeWatcher::new(loader).with_debounce(Duration::from_millis(200));
+        let task = tokio::spawn(watcher.run(output));
+        // Give the watches time to be set up
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        for background in ["#3b4252", "#434c5e", "#4c566a"] {
+            write_css(background);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let theme = tokio::time::timeout(Duration::from_secs(5), themes.next()).await.unwrap().unwrap();
+        assert_eq!(theme.background.to_hex(), "#4c566a");
+        assert!(tokio::time::timeout(Duration::from_millis(600), themes.next()).await.is_err());
+
+        task.abort();
+    }
+}